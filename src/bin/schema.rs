@@ -1,10 +1,21 @@
-use cosmwasm_schema::write_api;
-use auctionhouse::msg::{ExecMsg, InstantiateMsg, QueryMsg};
+use std::env::current_dir;
+
+use cosmwasm_schema::{export_schema, write_api};
+use auctionhouse::events::event_schema;
+use auctionhouse::msg::{ExecMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecMsg,
         query: QueryMsg,
+        migrate: MigrateMsg,
     }
-}
\ No newline at end of file
+
+    //Events are not part of the API messages, they are exported next to them for indexers as they are emitted.
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    out_dir.push("raw");
+
+    export_schema(&event_schema(), &out_dir);
+}
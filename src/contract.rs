@@ -1,50 +1,441 @@
-use cosmwasm_std::{Addr, DepsMut, Response, StdResult};
-use cw2::set_contract_version;
-
-use crate::state::{AUCTIONS, OWNERS};
+use archway_bindings::{
+    types::rewards::ContractMetadataResponse, ArchwayMsg, ArchwayQuery, ArchwayResult,
+};
+use cosmwasm_std::{coin, Addr, Coin, DepsMut, Env, Response, StdResult, Storage};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+
+use crate::{
+    error::ContractError,
+    msg::{InstantiateMsg, LegacyConfig, MigrateMsg},
+    state::{
+        auctions, Activity, Auction, Config, CONFIG, EPOCH_REWARDS, ESCROWED_TOKENS,
+        LEGACY_AUCTIONS, LEGACY_OWNERS,
+    },
+};
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub fn instantiate(deps: DepsMut, sender: Addr) -> StdResult<Response> {
+//100% expressed in basis points
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+pub fn instantiate(
+    deps: DepsMut<ArchwayQuery>,
+    env: Env,
+    sender: Addr,
+    msg: InstantiateMsg,
+) -> ArchwayResult<ContractError> {
     //Set name and version of auction house contract
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    deps.api.addr_validate(&sender.clone().into_string())?;
+    let mut owners: Vec<Addr> = Vec::new();
+
+    for owner in msg.owners {
+        let owner = deps.api.addr_validate(&owner)?;
+
+        if !owners.contains(&owner) {
+            owners.push(owner);
+        }
+    }
+
+    //Rewards stay in the auction house unless another address is given.
+    let rewards_address = match msg.rewards_address {
+        Some(address) => deps.api.addr_validate(&address)?,
+        None => env.contract.address,
+    };
 
-    let mut owners = OWNERS.load(deps.storage)?;
+    let config = Config {
+        owners,
+        rewards_address: rewards_address.clone(),
+        fee_bps: msg.fee_bps,
+        fee_recipient: deps.api.addr_validate(&msg.fee_recipient)?,
+        min_duration: msg.min_duration,
+        max_duration: msg.max_duration,
+        accepted_denoms: msg.accepted_denoms,
+        threshold: msg.threshold,
+        participants_share_bps: msg.participants_share_bps,
+        min_increment_bps: msg.min_increment_bps,
+    };
 
-    owners.push(sender.clone());
-    //The instantiatior of this contract will also be the initial owner of it.
-    OWNERS.save(deps.storage, &owners)?;
+    validate_config(&config)?;
 
-    AUCTIONS.save(deps.storage, &Vec::new())?;
+    CONFIG.save(deps.storage, &config)?;
 
     let resp = Response::new()
+        .add_message(ArchwayMsg::update_rewards_address(rewards_address))
         .add_attribute("action", "Instantiating Action House")
-        .add_attribute("Owner", sender);
+        .add_attribute("instantiator", sender);
+
+    Ok(resp)
+}
+
+//Upgrades are only allowed from an older (or the same) version of this contract.
+pub fn migrate(
+    mut deps: DepsMut<ArchwayQuery>,
+    env: Env,
+    msg: MigrateMsg,
+) -> ArchwayResult<ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::WrongContract(stored.contract));
+    }
+
+    let stored_version: Version = stored
+        .version
+        .parse()
+        .map_err(|err: semver::Error| ContractError::InvalidVersion(err.to_string()))?;
+    let new_version: Version = CONTRACT_VERSION
+        .parse()
+        .map_err(|err: semver::Error| ContractError::InvalidVersion(err.to_string()))?;
+
+    if stored_version > new_version {
+        return Err(ContractError::CannotDowngrade(stored.version));
+    }
+
+    let mut resp = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    //Houses deployed before 0.2.0 kept their owners and auctions in two vectors and had no config.
+    if CONFIG.may_load(deps.storage)?.is_none() {
+        let legacy_config = msg
+            .legacy_config
+            .ok_or(ContractError::MissingLegacyConfig)?;
+
+        let migrated = migrate_legacy_storage(deps.branch(), env, legacy_config)?;
+
+        resp = resp.add_attribute("migrated_auctions", migrated.to_string());
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(resp)
 }
 
+//Moves the legacy `Item<Vec<Auction>>` into the auctions map, indexing their tokens, and builds the config from the legacy
+//owners.
+//Returns the number of auctions moved.
+fn migrate_legacy_storage(
+    deps: DepsMut<ArchwayQuery>,
+    env: Env,
+    legacy_config: LegacyConfig,
+) -> Result<usize, ContractError> {
+    let owners = LEGACY_OWNERS.may_load(deps.storage)?.unwrap_or_default();
+
+    //The rewards address was only stored in the Archway metadata, fall back to the house itself if there is none.
+    let metadata: Option<ContractMetadataResponse> = deps
+        .querier
+        .query(&ArchwayQuery::contract_metadata(env.contract.address.clone()).into())
+        .ok();
+
+    let rewards_address = match metadata {
+        Some(metadata) if !metadata.rewards_address.is_empty() => {
+            deps.api.addr_validate(&metadata.rewards_address)?
+        }
+        _ => env.contract.address,
+    };
+
+    let config = Config {
+        owners,
+        rewards_address,
+        fee_bps: legacy_config.fee_bps,
+        fee_recipient: deps.api.addr_validate(&legacy_config.fee_recipient)?,
+        min_duration: legacy_config.min_duration,
+        max_duration: legacy_config.max_duration,
+        accepted_denoms: legacy_config.accepted_denoms,
+        //Any single owner could act on its own before proposals existed
+        threshold: 1,
+        participants_share_bps: 0,
+        min_increment_bps: 0,
+    };
+
+    validate_config(&config)?;
+
+    CONFIG.save(deps.storage, &config)?;
+    LEGACY_OWNERS.remove(deps.storage);
+
+    let legacy_auctions = LEGACY_AUCTIONS.may_load(deps.storage)?.unwrap_or_default();
+
+    for auction in legacy_auctions.iter() {
+        auctions().save(deps.storage, &auction.nft_id, auction)?;
+        escrow_tokens(deps.storage, auction)?;
+    }
+
+    LEGACY_AUCTIONS.remove(deps.storage);
+
+    Ok(legacy_auctions.len())
+}
+
+//Indexes every token of an open auction, so that it can't be listed again until the auction ends.
+pub fn escrow_tokens(storage: &mut dyn Storage, auction: &Auction) -> StdResult<()> {
+    for token in auction.tokens() {
+        ESCROWED_TOKENS.save(storage, (&token.nft_contract, &token.nft_id), &auction.nft_id)?;
+    }
+
+    Ok(())
+}
+
+//Checks shared by instantiation and config updates.
+pub fn validate_config(config: &Config) -> Result<(), ContractError> {
+    if config.owners.is_empty() {
+        return Err(ContractError::NoOwner);
+    }
+
+    if config.threshold == 0 || config.threshold as usize > config.owners.len() {
+        return Err(ContractError::InvalidThreshold);
+    }
+
+    if config.fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::InvalidFee);
+    }
+
+    if config.participants_share_bps > MAX_FEE_BPS {
+        return Err(ContractError::InvalidShare);
+    }
+
+    if config.min_increment_bps > MAX_FEE_BPS {
+        return Err(ContractError::InvalidIncrement);
+    }
+
+    if config.max_duration == 0 || config.min_duration > config.max_duration {
+        return Err(ContractError::InvalidDuration);
+    }
+
+    if config.accepted_denoms.is_empty() {
+        return Err(ContractError::NoDenoms);
+    }
+
+    Ok(())
+}
+
+//Share of the rewards of a closed epoch earned by an activity. Nothing is earned while the epoch is still open.
+pub fn epoch_share(storage: &dyn Storage, activity: &Activity) -> StdResult<Vec<Coin>> {
+    let epoch_rewards = match EPOCH_REWARDS.may_load(storage, activity.epoch)? {
+        Some(epoch_rewards) if epoch_rewards.total_points > 0 => epoch_rewards,
+        _ => return Ok(vec![]),
+    };
+
+    let share = epoch_rewards
+        .rewards
+        .into_iter()
+        .map(|reward| {
+            let amount = reward
+                .amount
+                .multiply_ratio(activity.points, epoch_rewards.total_points);
+
+            coin(amount.u128(), reward.denom)
+        })
+        .filter(|reward| !reward.amount.is_zero())
+        .collect();
+
+    Ok(share)
+}
+
 pub mod query {
     use archway_bindings::{
-        types::rewards::{ContractMetadataResponse, RewardsRecordsResponse},
+        types::rewards::{ContractMetadataResponse, FlatFeeResponse, RewardsRecordsResponse},
         ArchwayQuery, PageRequest,
     };
-    use cosmwasm_std::{Deps, Env, StdResult};
+    use cosmwasm_std::{Deps, Env, Order, StdResult};
+    use cw_storage_plus::Bound;
     use cw_utils::NativeBalance;
 
+    use super::epoch_share;
     use crate::{
-        msg::{OpenAuctionsResp, OutstandingRewardsResponse},
-        state::AUCTIONS,
+        msg::{
+            AccruedRewardsResp, ArchivedAuctionsResp, BestCollectionOfferResp,
+            CollectionOffersResp, FailedSettlement, FailedSettlementsResp, HooksResp, OffersResp,
+            OpenAuctionsResp, OutstandingRewardsResponse, PausedResp, ProposalsResp,
+            RewardsSplitResp, RoleHolder, RolesResp, WithdrawalsResp,
+        },
+        state::{
+            auctions, collection_offers, offers, ArchivedAuction, Auction, CollectionOffer, Config,
+            Offer, Proposal, ACTIVITY, ARCHIVE, CLAIMABLE, CONFIG, CURRENT_EPOCH,
+            FAILED_SETTLEMENTS, HOOKS, PAUSED, PROPOSALS, REWARDS_SPLIT, ROLES, WITHDRAWALS,
+        },
     };
 
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 30;
+
+    pub fn config(deps: Deps<ArchwayQuery>) -> StdResult<Config> {
+        CONFIG.load(deps.storage)
+    }
+
+    pub fn proposal(deps: Deps<ArchwayQuery>, proposal_id: u64) -> StdResult<Proposal> {
+        PROPOSALS.load(deps.storage, proposal_id)
+    }
+
+    //Proposals that are still waiting for approvals and have not expired, ordered by id.
+    pub fn pending_proposals(
+        deps: Deps<ArchwayQuery>,
+        env: Env,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ProposalsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let proposals = PROPOSALS
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .filter(|item| {
+                item.as_ref()
+                    .map_or(true, |(_, proposal)| !proposal.expires.is_expired(&env.block))
+            })
+            .take(limit)
+            .map(|item| item.map(|(_, proposal)| proposal))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ProposalsResp { proposals })
+    }
+
+    pub fn roles_of(deps: Deps<ArchwayQuery>, address: String) -> StdResult<RoleHolder> {
+        let address = deps.api.addr_validate(&address)?;
+        let roles = ROLES.may_load(deps.storage, &address)?.unwrap_or_default();
+
+        Ok(RoleHolder { address, roles })
+    }
+
+    //Every address holding at least one role, ordered by address.
+    pub fn roles(
+        deps: Deps<ArchwayQuery>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RolesResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+
+        let holders = ROLES
+            .range(
+                deps.storage,
+                start_after.as_ref().map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(address, roles)| RoleHolder { address, roles }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(RolesResp { holders })
+    }
+
+    pub fn auction(deps: Deps<ArchwayQuery>, nft_id: String) -> StdResult<Auction> {
+        auctions().load(deps.storage, &nft_id)
+    }
+
     //We return the current auctions that are still open and/or unclaimed.
-    pub fn open_auctions(deps: Deps<ArchwayQuery>) -> StdResult<OpenAuctionsResp> {
-        let auctions = AUCTIONS.load(deps.storage)?;
+    pub fn open_auctions(
+        deps: Deps<ArchwayQuery>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OpenAuctionsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let open = auctions()
+            .range(
+                deps.storage,
+                start_after.as_deref().map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, auction)| auction))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(OpenAuctionsResp { auctions: open })
+    }
+
+    pub fn upcoming_auctions(
+        deps: Deps<ArchwayQuery>,
+        env: Env,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OpenAuctionsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        //Auctions starting after the current block, resuming after the last auction of the previous page
+        let mut start = Bound::inclusive((env.block.time.seconds() + 1, String::new()));
+
+        if let Some(nft_id) = start_after {
+            let last = auctions().may_load(deps.storage, &nft_id)?;
+
+            if let Some(start_time) = last.and_then(|auction| auction.start_time) {
+                if start_time > env.block.time {
+                    start = Bound::exclusive((start_time.seconds(), nft_id));
+                }
+            }
+        }
+
+        let upcoming = auctions()
+            .idx
+            .start_time
+            .range(deps.storage, Some(start), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, auction)| auction))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(OpenAuctionsResp { auctions: upcoming })
+    }
+
+    pub fn paused(deps: Deps<ArchwayQuery>) -> StdResult<PausedResp> {
+        let operations = PAUSED.may_load(deps.storage)?.unwrap_or_default();
+
+        Ok(PausedResp { operations })
+    }
+
+    pub fn failed_settlements(
+        deps: Deps<ArchwayQuery>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<FailedSettlementsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let settlements = FAILED_SETTLEMENTS
+            .range(
+                deps.storage,
+                start_after.as_deref().map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(nft_id, reason)| FailedSettlement { nft_id, reason }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(FailedSettlementsResp { settlements })
+    }
+
+    pub fn archived_auction(deps: Deps<ArchwayQuery>, id: u64) -> StdResult<ArchivedAuction> {
+        ARCHIVE.load(deps.storage, id)
+    }
 
-        Ok(OpenAuctionsResp { auctions })
+    pub fn archived_auctions(
+        deps: Deps<ArchwayQuery>,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ArchivedAuctionsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let archived = ARCHIVE
+            .range(
+                deps.storage,
+                None,
+                start_before.map(Bound::exclusive),
+                Order::Descending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, auction)| auction))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ArchivedAuctionsResp { auctions: archived })
     }
 
     //We get the owner address and rewards address
@@ -56,12 +447,9 @@ pub mod query {
         deps.querier.query(&req)
     }
 
-    //Check unclaimed rewards
-    pub fn outstanding_rewards(
-        deps: Deps<ArchwayQuery>,
-        env: Env,
-    ) -> StdResult<OutstandingRewardsResponse> {
-        let rewards_address = env.contract.address;
+    //Check unclaimed rewards of the configured rewards address
+    pub fn outstanding_rewards(deps: Deps<ArchwayQuery>) -> StdResult<OutstandingRewardsResponse> {
+        let rewards_address = CONFIG.load(deps.storage)?.rewards_address;
         let req = ArchwayQuery::rewards_records_with_pagination(
             rewards_address,
             PageRequest::new().count_total(),
@@ -84,526 +472,2908 @@ pub mod query {
             total_records,
         })
     }
+
+    pub fn flat_fee(deps: Deps<ArchwayQuery>, env: Env) -> StdResult<FlatFeeResponse> {
+        let req = ArchwayQuery::flat_fee(env.contract.address).into();
+
+        deps.querier.query(&req)
+    }
+
+    pub fn hooks(deps: Deps<ArchwayQuery>) -> StdResult<HooksResp> {
+        let hooks = HOOKS
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        Ok(HooksResp { hooks })
+    }
+
+    pub fn rewards_split(deps: Deps<ArchwayQuery>) -> StdResult<RewardsSplitResp> {
+        let recipients = REWARDS_SPLIT.may_load(deps.storage)?.unwrap_or_default();
+
+        Ok(RewardsSplitResp { recipients })
+    }
+
+    pub fn withdrawals(
+        deps: Deps<ArchwayQuery>,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<WithdrawalsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let withdrawals = WITHDRAWALS
+            .range(
+                deps.storage,
+                None,
+                start_before.map(Bound::exclusive),
+                Order::Descending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, withdrawal)| withdrawal))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(WithdrawalsResp { withdrawals })
+    }
+
+    //Points of an epoch that was closed since the last activity of the address are already counted as claimable.
+    pub fn accrued_rewards(
+        deps: Deps<ArchwayQuery>,
+        address: String,
+    ) -> StdResult<AccruedRewardsResp> {
+        let address = deps.api.addr_validate(&address)?;
+
+        let epoch = CURRENT_EPOCH.may_load(deps.storage)?.unwrap_or_default();
+        let activity = ACTIVITY.may_load(deps.storage, &address)?.unwrap_or_default();
+
+        let mut claimable =
+            NativeBalance(CLAIMABLE.may_load(deps.storage, &address)?.unwrap_or_default());
+
+        let pending_points = if activity.epoch == epoch.id {
+            activity.points
+        } else {
+            for reward in epoch_share(deps.storage, &activity)? {
+                claimable += reward;
+            }
+
+            0
+        };
+
+        claimable.normalize();
+
+        Ok(AccruedRewardsResp {
+            claimable: claimable.into_vec(),
+            pending_points,
+        })
+    }
+
+    pub fn offer(deps: Deps<ArchwayQuery>, offer_id: u64) -> StdResult<Offer> {
+        offers().load(deps.storage, offer_id)
+    }
+
+    //Offers made on a single token, ordered by offer id.
+    pub fn offers_by_token(
+        deps: Deps<ArchwayQuery>,
+        nft_contract: String,
+        nft_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResp> {
+        let iter = offers().idx.token.prefix((nft_contract, nft_id)).range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        );
+
+        Ok(OffersResp {
+            offers: paginate_offers(iter, limit)?,
+        })
+    }
+
+    //Offers made on any token of a collection, ordered by offer id.
+    pub fn offers_by_collection(
+        deps: Deps<ArchwayQuery>,
+        nft_contract: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResp> {
+        let iter = offers().idx.collection.prefix(nft_contract).range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        );
+
+        Ok(OffersResp {
+            offers: paginate_offers(iter, limit)?,
+        })
+    }
+
+    //Offers made by a buyer, ordered by offer id.
+    pub fn offers_by_buyer(
+        deps: Deps<ArchwayQuery>,
+        buyer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResp> {
+        let buyer = deps.api.addr_validate(&buyer)?;
+
+        let iter = offers().idx.buyer.prefix(buyer).range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        );
+
+        Ok(OffersResp {
+            offers: paginate_offers(iter, limit)?,
+        })
+    }
+
+    fn paginate_offers<'a, O>(
+        iter: Box<dyn Iterator<Item = StdResult<(u64, O)>> + 'a>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<O>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        iter.take(limit)
+            .map(|item| item.map(|(_, offer)| offer))
+            .collect()
+    }
+
+    pub fn collection_offer(deps: Deps<ArchwayQuery>, offer_id: u64) -> StdResult<CollectionOffer> {
+        collection_offers().load(deps.storage, offer_id)
+    }
+
+    //Collection offers made on a collection, ordered by offer id.
+    pub fn collection_offers_by_collection(
+        deps: Deps<ArchwayQuery>,
+        nft_contract: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<CollectionOffersResp> {
+        let iter = collection_offers().idx.collection.prefix(nft_contract).range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        );
+
+        Ok(CollectionOffersResp {
+            offers: paginate_offers(iter, limit)?,
+        })
+    }
+
+    //Collection offers made by a buyer, ordered by offer id.
+    pub fn collection_offers_by_buyer(
+        deps: Deps<ArchwayQuery>,
+        buyer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<CollectionOffersResp> {
+        let buyer = deps.api.addr_validate(&buyer)?;
+
+        let iter = collection_offers().idx.buyer.prefix(buyer).range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        );
+
+        Ok(CollectionOffersResp {
+            offers: paginate_offers(iter, limit)?,
+        })
+    }
+
+    //The best collection offer is the highest price per token in the given denom among the ones that are still valid.
+    //On equal prices the oldest offer wins. Expired offers are skipped however many of them rank above the best valid one,
+    //they stay in the index until their buyers cancel them.
+    pub fn best_collection_offer(
+        deps: Deps<ArchwayQuery>,
+        env: Env,
+        nft_contract: String,
+        denom: String,
+    ) -> StdResult<BestCollectionOfferResp> {
+        let iter = collection_offers()
+            .idx
+            .price
+            .sub_prefix((nft_contract, denom))
+            .range(deps.storage, None, None, Order::Ascending);
+
+        let best = iter
+            .map(|item| item.map(|(_, offer)| offer))
+            .find(|item| {
+                item.as_ref()
+                    .map_or(true, |offer| !offer.expires.is_expired(&env.block))
+            })
+            .transpose()?;
+
+        Ok(BestCollectionOfferResp { offer: best })
+    }
 }
 
-pub mod exec {
-    use archway_bindings::{ArchwayMsg, ArchwayQuery, ArchwayResult};
-    use cosmwasm_std::{
-        coin, to_binary, Addr, BankMsg, Coin, DepsMut, Env, QueryRequest, Response, Timestamp,
-        WasmMsg, WasmQuery,
-    };
-    use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, OwnerOfResponse};
+pub mod exec {
+    use archway_bindings::{ArchwayMsg, ArchwayQuery, ArchwayResult};
+    use cosmwasm_std::{
+        coin, from_binary, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, Order,
+        QueryRequest, Response, StdError, StdResult, Storage, SubMsg, Timestamp, Uint128, WasmMsg,
+        WasmQuery,
+    };
+    use cw_storage_plus::PrefixBound;
+    use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, Cw721ReceiveMsg, OwnerOfResponse, TokensResponse};
+    use cw_utils::{Expiration, NativeBalance};
+    use sha2::{Digest, Sha256};
+
+    use crate::{
+        error::ContractError,
+        events::AuctionEvent,
+        hooks::AuctionHookMsg,
+        msg::{
+            AuctionParams, AuctionUpdate, BatchAuctionEntry, BidderGate, ExecMsg, OwnerAction,
+            ReceiveMsg, RewardsRecipient,
+        },
+        state::{
+            auctions, collection_offers, offers, Activity, ArchivedAuction, Auction, BidRecord,
+            CollectionOffer, Config, Flag, Gate, LotToken, Offer, Operation, Outcome, Proposal,
+            Role, SplitRecipient, WithdrawalMode, ACTIVITY, ARCHIVE, ARCHIVE_COUNT,
+            AUCTION_ACTIVITY, CLAIMABLE, COLLECTION_OFFER_COUNT, CONFIG, CURRENT_EPOCH, DEPOSITS,
+            ESCROWED_TOKENS, FAILED_SETTLEMENTS, HOOKS, OFFER_COUNT, PAUSED, PENDING_WITHDRAWAL,
+            PROPOSALS, PROPOSAL_COUNT, REWARDS_SPLIT, ROLES, SETTLE_QUEUE,
+        },
+    };
+
+    use super::{
+        epoch_share, escrow_tokens, query,
+        reply::{HOOK_REPLY_ID, SETTLE_REPLY_ID, WITHDRAW_REPLY_ID},
+        validate_config, MAX_FEE_BPS,
+    };
+
+    //Auctions last 72h unless the seller picks another duration
+    const DEFAULT_DURATION: u64 = 72 * 60 * 60;
+
+    //Maximum number of auctions settled by a single `settle_expired` call
+    const MAX_SETTLE_LIMIT: u32 = 30;
+
+    //Points earned by the seller and by the bidder or buyer for every bid and every sale
+    const ACTIVITY_POINTS: u64 = 1;
+
+    //Number of bids escrowed for an auction, the oldest outbid ones are refunded beyond it
+    const MAX_BID_HISTORY: usize = 10;
+
+    //Gas each hook contract can use to handle a notification
+    const HOOK_GAS_LIMIT: u64 = 200_000;
+
+    //Any owner can propose an owner action, the proposal counts as his approval. It is executed as soon as enough owners approved it.
+    pub fn propose(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        action: OwnerAction,
+        expires: Expiration,
+    ) -> ArchwayResult<ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+
+        if !config.owners.contains(&sender) {
+            return Err(ContractError::Unauthorized);
+        }
+
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::InvalidExpiration);
+        }
+
+        let proposal_id = PROPOSAL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+        PROPOSAL_COUNT.save(deps.storage, &proposal_id)?;
+
+        let proposal = Proposal {
+            id: proposal_id,
+            proposer: sender.clone(),
+            action,
+            approvals: vec![sender],
+            expires,
+        };
+
+        let resp = Response::new()
+            .add_attribute("method", "propose")
+            .add_attribute("proposal_id", proposal_id.to_string());
+
+        execute_if_approved(deps, env, &config, proposal, resp)
+    }
+
+    //Any owner can approve a pending proposal once, before it expires.
+    pub fn approve(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        proposal_id: u64,
+    ) -> ArchwayResult<ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+
+        if !config.owners.contains(&sender) {
+            return Err(ContractError::Unauthorized);
+        }
+
+        let mut proposal = PROPOSALS
+            .may_load(deps.storage, proposal_id)?
+            .ok_or(ContractError::NoProposal)?;
+
+        if proposal.expires.is_expired(&env.block) {
+            return Err(ContractError::ProposalExpired);
+        }
+
+        if proposal.approvals.contains(&sender) {
+            return Err(ContractError::AlreadyApproved);
+        }
+
+        proposal.approvals.push(sender);
+
+        let resp = Response::new()
+            .add_attribute("method", "approve")
+            .add_attribute("proposal_id", proposal_id.to_string());
+
+        execute_if_approved(deps, env, &config, proposal, resp)
+    }
+
+    //Only approvals of addresses that are still owners count towards the threshold.
+    fn execute_if_approved(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        config: &Config,
+        proposal: Proposal,
+        resp: Response<ArchwayMsg>,
+    ) -> ArchwayResult<ContractError> {
+        let approvals = proposal
+            .approvals
+            .iter()
+            .filter(|owner| config.owners.contains(owner))
+            .count();
+
+        if approvals < config.threshold as usize {
+            PROPOSALS.save(deps.storage, proposal.id, &proposal)?;
+
+            let resp = resp
+                .add_attribute("status", "pending")
+                .add_attribute("approvals", approvals.to_string());
+
+            return Ok(resp);
+        }
+
+        PROPOSALS.remove(deps.storage, proposal.id);
+
+        let action_resp = match proposal.action {
+            OwnerAction::AddOwner { new_owner } => add_owner(deps, new_owner),
+            OwnerAction::RemoveOwner { old_owner } => remove_owner(deps, old_owner),
+            OwnerAction::UpdateThreshold { threshold } => update_threshold(deps, threshold),
+            OwnerAction::UpdateConfig {
+                min_duration,
+                max_duration,
+                accepted_denoms,
+                participants_share_bps,
+                min_increment_bps,
+                fee_recipient,
+            } => update_config(
+                deps,
+                min_duration,
+                max_duration,
+                accepted_denoms,
+                participants_share_bps,
+                min_increment_bps,
+                fee_recipient,
+            ),
+            OwnerAction::UpdateRewardsAddress { address } => {
+                update_rewards_address(deps, env, address)
+            }
+            OwnerAction::UpdateRewardsSplit { recipients } => {
+                update_rewards_split(deps, recipients)
+            }
+            OwnerAction::AddHook { address } => add_hook(deps, address),
+            OwnerAction::RemoveHook { address } => remove_hook(deps, address),
+            OwnerAction::SetFlatFee { amount } => set_flat_fee(env, amount),
+            OwnerAction::GrantRole { address, role } => {
+                let address = deps.api.addr_validate(&address)?;
+                set_role(deps, address, role, true)
+            }
+            OwnerAction::RevokeRole { address, role } => {
+                let address = deps.api.addr_validate(&address)?;
+                set_role(deps, address, role, false)
+            }
+            OwnerAction::ForceCancel {
+                nft_id,
+                reason,
+                nft_receiver,
+            } => {
+                let nft_receiver = deps.api.addr_validate(&nft_receiver)?;
+                let auction = auctions()
+                    .may_load(deps.storage, &nft_id)?
+                    .ok_or(ContractError::NoAuction)?;
+
+                cancel_auction(deps, env, proposal.proposer, auction, reason, nft_receiver)
+            }
+        }?;
+
+        let resp = resp
+            .add_attribute("status", "executed")
+            .add_submessages(action_resp.messages)
+            .add_attributes(action_resp.attributes)
+            .add_events(action_resp.events);
+
+        Ok(resp)
+    }
+
+    //Admins can grant every role but admin to other addresses. Only the owners can appoint admins.
+    pub fn grant_role(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+        address: String,
+        role: Role,
+    ) -> ArchwayResult<ContractError> {
+        check_role(deps.storage, &sender, Role::Admin)?;
+
+        let address = deps.api.addr_validate(&address)?;
+
+        //An admin can't change his own roles
+        if role == Role::Admin || address == sender {
+            return Err(ContractError::Unauthorized);
+        }
+
+        set_role(deps, address, role, true)
+    }
+
+    //Admins can revoke every role but admin from other addresses. Only the owners can remove admins.
+    pub fn revoke_role(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+        address: String,
+        role: Role,
+    ) -> ArchwayResult<ContractError> {
+        check_role(deps.storage, &sender, Role::Admin)?;
+
+        let address = deps.api.addr_validate(&address)?;
+
+        if role == Role::Admin || address == sender {
+            return Err(ContractError::Unauthorized);
+        }
+
+        set_role(deps, address, role, false)
+    }
+
+    fn set_role(
+        deps: DepsMut<ArchwayQuery>,
+        address: Addr,
+        role: Role,
+        granted: bool,
+    ) -> ArchwayResult<ContractError> {
+        let mut roles = ROLES.may_load(deps.storage, &address)?.unwrap_or_default();
+
+        roles.retain(|held| *held != role);
+
+        if granted {
+            roles.push(role);
+        }
+
+        if roles.is_empty() {
+            ROLES.remove(deps.storage, &address);
+        } else {
+            ROLES.save(deps.storage, &address, &roles)?;
+        }
+
+        let res = Response::new()
+            .add_attribute("method", if granted { "grant_role" } else { "revoke_role" })
+            .add_attribute("address", address)
+            .add_attribute("role", role.to_string());
+
+        Ok(res)
+    }
+
+    fn check_role(storage: &dyn Storage, address: &Addr, role: Role) -> Result<(), ContractError> {
+        let roles = ROLES.may_load(storage, address)?.unwrap_or_default();
+
+        if !roles.contains(&role) {
+            return Err(ContractError::MissingRole(role));
+        }
+
+        Ok(())
+    }
+
+    //Pausing has to be quick, so any pauser can do it without going through a proposal. Like every role, owners hold it
+    //once they granted it to themselves.
+    pub fn set_paused(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+        operations: Vec<Operation>,
+        paused: bool,
+    ) -> ArchwayResult<ContractError> {
+        check_role(deps.storage, &sender, Role::Pauser)?;
+
+        let mut current = PAUSED.may_load(deps.storage)?.unwrap_or_default();
+
+        current.retain(|operation| !operations.contains(operation));
+
+        if paused {
+            current.extend(operations.iter().copied());
+        }
+
+        PAUSED.save(deps.storage, &current)?;
+
+        let mut res =
+            Response::new().add_attribute("method", if paused { "pause" } else { "unpause" });
+
+        for operation in operations {
+            res = res.add_attribute("operation", operation.to_string());
+        }
+
+        Ok(res)
+    }
+
+    fn check_not_paused(storage: &dyn Storage, operation: Operation) -> Result<(), ContractError> {
+        let paused = PAUSED.may_load(storage)?.unwrap_or_default();
+
+        if paused.contains(&operation) {
+            return Err(ContractError::Paused(operation));
+        }
+
+        Ok(())
+    }
+
+    //The owners can modify where the rewards accumulated by the contract will be sent to when they are withdrawn. It takes
+    //a proposal since whoever controls the rewards address gets the rewards.
+    fn update_rewards_address(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        rewards_address: Option<String>,
+    ) -> ArchwayResult<ContractError> {
+        let rewards_address = match rewards_address {
+            Some(address) => deps.api.addr_validate(&address)?,
+            None => env.contract.address,
+        };
+
+        let mut config = CONFIG.load(deps.storage)?;
+
+        config.rewards_address = rewards_address.clone();
+
+        CONFIG.save(deps.storage, &config)?;
+
+        let msg = ArchwayMsg::update_rewards_address(rewards_address);
+
+        let res = Response::new()
+            .add_message(msg)
+            .add_attribute("method", "update_rewards_address");
+
+        Ok(res)
+    }
+
+    //Rewards managers can withdraw the rewards to the reward address set up by the owners (This can be a wallet or ideally a contract that distributes rewards accordingly
+    //if there are multiple rewards receivers (optionally using a ratio)).
+    pub fn withdraw_rewards(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+        limit: Option<u64>,
+        record_ids: Option<Vec<u64>>,
+    ) -> ArchwayResult<ContractError> {
+        check_role(deps.storage, &sender, Role::RewardsManager)?;
+
+        let mode = match (limit, record_ids) {
+            (Some(_), Some(_)) => return Err(ContractError::ConflictingWithdrawal),
+            (_, Some(ids)) => WithdrawalMode::RecordIds { ids },
+            (limit, None) => WithdrawalMode::Limit {
+                limit: limit.unwrap_or(0),
+            },
+        };
+
+        let withdraw_msg = match &mode {
+            WithdrawalMode::Limit { limit } => ArchwayMsg::withdraw_rewards_by_limit(*limit),
+            WithdrawalMode::RecordIds { ids } => ArchwayMsg::withdraw_rewards_by_ids(ids.clone()),
+        };
+
+        PENDING_WITHDRAWAL.save(deps.storage, &mode)?;
+
+        //The withdrawn rewards are logged and shared in the reply, see `reply::rewards_withdrawn`.
+        let msg = SubMsg::reply_on_success(withdraw_msg, WITHDRAW_REPLY_ID);
+
+        let res = Response::new()
+            .add_submessage(msg)
+            .add_attribute("method", "withdraw_rewards");
+
+        Ok(res)
+    }
+
+    //Same withdrawal as `withdraw_rewards`, open to anyone since the rewards can only go to the configured recipients.
+    pub fn distribute_rewards(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+    ) -> ArchwayResult<ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+
+        if config.rewards_address != env.contract.address {
+            return Err(ContractError::RewardsNotHeld);
+        }
+
+        if REWARDS_SPLIT.may_load(deps.storage)?.unwrap_or_default().is_empty() {
+            return Err(ContractError::NoRewardsSplit);
+        }
+
+        PENDING_WITHDRAWAL.save(deps.storage, &WithdrawalMode::Limit { limit: 0 })?;
+
+        let msg = SubMsg::reply_on_success(
+            ArchwayMsg::withdraw_rewards_by_limit(0),
+            WITHDRAW_REPLY_ID,
+        );
+
+        let res = Response::new()
+            .add_submessage(msg)
+            .add_attribute("method", "distribute_rewards");
+
+        Ok(res)
+    }
+
+    fn add_hook(deps: DepsMut<ArchwayQuery>, address: String) -> ArchwayResult<ContractError> {
+        let hook = deps.api.addr_validate(&address)?;
+        let mut hooks = HOOKS.may_load(deps.storage)?.unwrap_or_default();
+
+        if hooks.contains(&hook) {
+            return Err(ContractError::HookAlreadyRegistered);
+        }
+
+        hooks.push(hook.clone());
+        HOOKS.save(deps.storage, &hooks)?;
+
+        let res = Response::new()
+            .add_attribute("method", "add_hook")
+            .add_attribute("hook", hook);
+
+        Ok(res)
+    }
+
+    fn remove_hook(deps: DepsMut<ArchwayQuery>, address: String) -> ArchwayResult<ContractError> {
+        let hook = deps.api.addr_validate(&address)?;
+        let mut hooks = HOOKS.may_load(deps.storage)?.unwrap_or_default();
+
+        if !hooks.contains(&hook) {
+            return Err(ContractError::HookNotRegistered);
+        }
+
+        hooks.retain(|h| *h != hook);
+        HOOKS.save(deps.storage, &hooks)?;
+
+        let res = Response::new()
+            .add_attribute("method", "remove_hook")
+            .add_attribute("hook", hook);
+
+        Ok(res)
+    }
+
+    //Hooks are sub messages replying on error with a gas limit, so a failing hook can't block the auction.
+    fn hook_msgs(
+        storage: &dyn Storage,
+        msg: AuctionHookMsg,
+    ) -> StdResult<Vec<SubMsg<ArchwayMsg>>> {
+        HOOKS
+            .may_load(storage)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|hook| {
+                let msg = msg.clone().into_cosmos_msg(hook)?;
+                Ok(SubMsg::reply_on_error(msg, HOOK_REPLY_ID).with_gas_limit(HOOK_GAS_LIMIT))
+            })
+            .collect()
+    }
+
+    fn listed_hook_msgs(
+        storage: &dyn Storage,
+        auction: &Auction,
+    ) -> StdResult<Vec<SubMsg<ArchwayMsg>>> {
+        let msg = AuctionHookMsg::Listed {
+            auction_id: auction.nft_id.clone(),
+            collection: auction.nft_contract.clone(),
+            seller: auction.owner.clone(),
+        };
+
+        hook_msgs(storage, msg)
+    }
+
+    //Archway charges the flat fee on top of the gas of every message sent to the house and pays it to the rewards address.
+    fn set_flat_fee(env: Env, amount: Coin) -> ArchwayResult<ContractError> {
+        let msg = ArchwayMsg::set_flat_fee(env.contract.address, amount.clone());
+
+        let res = Response::new()
+            .add_message(msg)
+            .add_attribute("method", "set_flat_fee")
+            .add_attribute("flat_fee", amount.to_string());
+
+        Ok(res)
+    }
+
+    fn update_rewards_split(
+        deps: DepsMut<ArchwayQuery>,
+        recipients: Vec<RewardsRecipient>,
+    ) -> ArchwayResult<ContractError> {
+        let mut split: Vec<SplitRecipient> = Vec::with_capacity(recipients.len());
+
+        for recipient in recipients {
+            let address = deps.api.addr_validate(&recipient.address)?;
+
+            if recipient.weight_bps == 0 || split.iter().any(|r| r.address == address) {
+                return Err(ContractError::InvalidSplit);
+            }
+
+            split.push(SplitRecipient {
+                address,
+                weight_bps: recipient.weight_bps,
+            });
+        }
+
+        let total: u32 = split.iter().map(|r| r.weight_bps as u32).sum();
+
+        if !split.is_empty() && total != MAX_FEE_BPS as u32 {
+            return Err(ContractError::InvalidSplit);
+        }
+
+        REWARDS_SPLIT.save(deps.storage, &split)?;
+
+        let res = Response::new()
+            .add_attribute("method", "update_rewards_split")
+            .add_attribute("recipients", split.len().to_string());
+
+        Ok(res)
+    }
+
+    //Anyone can claim the rewards accrued by his activity in closed epochs.
+    pub fn claim_rewards(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+    ) -> ArchwayResult<ContractError> {
+        let epoch = CURRENT_EPOCH.may_load(deps.storage)?.unwrap_or_default();
+
+        let activity = settle_activity(deps.storage, &sender, epoch.id)?;
+        ACTIVITY.save(deps.storage, &sender, &activity)?;
+
+        let claimable = CLAIMABLE.may_load(deps.storage, &sender)?.unwrap_or_default();
+
+        if claimable.is_empty() {
+            return Err(ContractError::NothingToClaim);
+        }
+
+        CLAIMABLE.remove(deps.storage, &sender);
+
+        let send_rewards_msg = BankMsg::Send {
+            to_address: sender.to_string(),
+            amount: claimable,
+        };
+
+        let res = Response::new()
+            .add_message(send_rewards_msg)
+            .add_attribute("method", "claim_rewards")
+            .add_attribute("claimer", sender);
+
+        Ok(res)
+    }
+
+    //Credits points to an address in the current epoch, after converting the points it earned in a closed epoch.
+    fn record_activity(storage: &mut dyn Storage, address: &Addr, points: u64) -> StdResult<()> {
+        let mut epoch = CURRENT_EPOCH.may_load(storage)?.unwrap_or_default();
+
+        let mut activity = settle_activity(storage, address, epoch.id)?;
+
+        activity.points += points;
+        epoch.total_points += points;
+
+        ACTIVITY.save(storage, address, &activity)?;
+        CURRENT_EPOCH.save(storage, &epoch)
+    }
+
+    //Credits every bidder of an auction for his own bids and the seller for all of them. The seller and the winner are
+    //also credited for the settlement.
+    fn credit_activity(
+        storage: &mut dyn Storage,
+        auction: &Auction,
+        winner: &Addr,
+    ) -> StdResult<()> {
+        let bid_points = AUCTION_ACTIVITY
+            .prefix(&auction.nft_id)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut seller_points = ACTIVITY_POINTS;
+
+        for (bidder, points) in bid_points {
+            record_activity(storage, &bidder, points)?;
+            seller_points += points;
+        }
+
+        record_activity(storage, winner, ACTIVITY_POINTS)?;
+        record_activity(storage, &auction.owner, seller_points)
+    }
+
+    //Moves the share earned during a closed epoch to the claimable rewards and returns the activity for the current epoch.
+    fn settle_activity(
+        storage: &mut dyn Storage,
+        address: &Addr,
+        current_epoch: u64,
+    ) -> StdResult<Activity> {
+        let activity = ACTIVITY.may_load(storage, address)?.unwrap_or_default();
+
+        if activity.epoch == current_epoch {
+            return Ok(activity);
+        }
+
+        let share = epoch_share(storage, &activity)?;
+
+        if !share.is_empty() {
+            let mut claimable =
+                NativeBalance(CLAIMABLE.may_load(storage, address)?.unwrap_or_default());
+
+            for reward in share {
+                claimable += reward;
+            }
+
+            claimable.normalize();
+
+            CLAIMABLE.save(storage, address, &claimable.into_vec())?;
+        }
+
+        Ok(Activity {
+            epoch: current_epoch,
+            points: 0,
+        })
+    }
+
+    //The owners can add another owner that will be able to propose and approve owner actions.
+    fn add_owner(deps: DepsMut<ArchwayQuery>, new_owner: Addr) -> ArchwayResult<ContractError> {
+        deps.api.addr_validate(&new_owner.clone().into_string())?;
+
+        let mut config = CONFIG.load(deps.storage)?;
+
+        if !config.owners.contains(&new_owner.clone()) {
+            config.owners.push(new_owner.clone())
+        }
+
+        CONFIG.save(deps.storage, &config)?;
+
+        let res = Response::new()
+            .add_attribute("method", "add_owner")
+            .add_event(AuctionEvent::OwnerAdded { owner: new_owner }.into());
+
+        Ok(res)
+    }
+
+    //The owners can remove another owner as long as enough owners remain to reach the threshold.
+    fn remove_owner(deps: DepsMut<ArchwayQuery>, old_owner: Addr) -> ArchwayResult<ContractError> {
+        deps.api.addr_validate(&old_owner.clone().into_string())?;
+
+        let mut config = CONFIG.load(deps.storage)?;
+
+        config.owners.retain(|value| value.to_string() != old_owner.to_string());
+
+        validate_config(&config)?;
+
+        CONFIG.save(deps.storage, &config)?;
+
+        let res = Response::new()
+            .add_attribute("method", "remove_owner")
+            .add_event(AuctionEvent::OwnerRemoved { owner: old_owner }.into());
+
+        Ok(res)
+    }
+
+    //The owners can change how many approvals a proposal needs, between 1 and the number of owners.
+    fn update_threshold(
+        deps: DepsMut<ArchwayQuery>,
+        threshold: u32,
+    ) -> ArchwayResult<ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+
+        config.threshold = threshold;
+
+        validate_config(&config)?;
+
+        CONFIG.save(deps.storage, &config)?;
+
+        let res = Response::new()
+            .add_attribute("method", "update_threshold")
+            .add_attribute("threshold", threshold.to_string());
+
+        Ok(res)
+    }
+
+    //Fee managers can change the house fee and who receives it.
+    pub fn update_fees(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+        fee_bps: u16,
+    ) -> ArchwayResult<ContractError> {
+        check_role(deps.storage, &sender, Role::FeeManager)?;
+
+        let mut config = CONFIG.load(deps.storage)?;
+
+        config.fee_bps = fee_bps;
+
+        validate_config(&config)?;
+
+        CONFIG.save(deps.storage, &config)?;
+
+        let res = Response::new()
+            .add_attribute("method", "update_fees")
+            .add_attribute("fee_bps", config.fee_bps.to_string());
+
+        Ok(res)
+    }
+
+    //The owners can change the auction duration bounds, the accepted denoms, the participants share of the rewards, the
+    //minimum bid increment and the fee recipient.
+    fn update_config(
+        deps: DepsMut<ArchwayQuery>,
+        min_duration: Option<u64>,
+        max_duration: Option<u64>,
+        accepted_denoms: Option<Vec<String>>,
+        participants_share_bps: Option<u16>,
+        min_increment_bps: Option<u16>,
+        fee_recipient: Option<String>,
+    ) -> ArchwayResult<ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+
+        if let Some(min_duration) = min_duration {
+            config.min_duration = min_duration;
+        }
+
+        if let Some(max_duration) = max_duration {
+            config.max_duration = max_duration;
+        }
+
+        if let Some(accepted_denoms) = accepted_denoms {
+            config.accepted_denoms = accepted_denoms;
+        }
+
+        if let Some(participants_share_bps) = participants_share_bps {
+            config.participants_share_bps = participants_share_bps;
+        }
+
+        if let Some(min_increment_bps) = min_increment_bps {
+            config.min_increment_bps = min_increment_bps;
+        }
+
+        if let Some(fee_recipient) = fee_recipient {
+            config.fee_recipient = deps.api.addr_validate(&fee_recipient)?;
+        }
+
+        validate_config(&config)?;
+
+        CONFIG.save(deps.storage, &config)?;
+
+        let res = Response::new().add_attribute("method", "update_config");
+
+        Ok(res)
+    }
+
+    pub fn create_auction(
+        mut deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        nft_id: String,
+        nft_contract: String,
+        params: AuctionParams,
+    ) -> ArchwayResult<ContractError> {
+        let tokens = vec![LotToken {
+            nft_id,
+            nft_contract,
+        }];
+
+        let auction = open_auction(deps.branch(), env, sender, tokens, params)?;
+
+        let res = Response::new()
+            .add_submessages(listed_hook_msgs(deps.storage, &auction)?)
+            .add_attribute("method", "create_auction")
+            .add_attribute("nft_id", auction.nft_id.clone())
+            .add_event(AuctionEvent::listed(&auction).into());
+
+        Ok(res)
+    }
+
+    //A lot works like any other auction but escrows several NFTs, possibly from different collections.
+    pub fn create_lot_auction(
+        mut deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        tokens: Vec<LotToken>,
+        params: AuctionParams,
+    ) -> ArchwayResult<ContractError> {
+        let auction = open_auction(deps.branch(), env, sender, tokens, params)?;
+
+        let res = Response::new()
+            .add_submessages(listed_hook_msgs(deps.storage, &auction)?)
+            .add_attribute("method", "create_lot_auction")
+            .add_attribute("nft_id", auction.nft_id.clone())
+            .add_attribute("lot_size", auction.tokens().len().to_string())
+            .add_event(AuctionEvent::listed(&auction).into());
+
+        Ok(res)
+    }
+
+    //Every entry is listed with the same checks as `create_auction`, a single failure reverts the whole batch.
+    pub fn batch_create_auction(
+        mut deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        defaults: AuctionParams,
+        entries: Vec<BatchAuctionEntry>,
+    ) -> ArchwayResult<ContractError> {
+        if entries.is_empty() {
+            return Err(ContractError::EmptyBatch);
+        }
+
+        let mut resp = Response::new()
+            .add_attribute("method", "batch_create_auction")
+            .add_attribute("count", entries.len().to_string());
+
+        for entry in entries {
+            let tokens = vec![LotToken {
+                nft_id: entry.nft_id.clone(),
+                nft_contract: entry.nft_contract,
+            }];
+
+            let params = AuctionParams {
+                min_bid: entry.min_bid.unwrap_or(defaults.min_bid),
+                buyout: entry.buyout.unwrap_or(defaults.buyout),
+                denom: entry.denom.unwrap_or_else(|| defaults.denom.clone()),
+                duration: entry.duration.or(defaults.duration),
+                start_time: entry.start_time.or(defaults.start_time),
+                gate: entry.gate.or_else(|| defaults.gate.clone()),
+            };
+
+            let auction = open_auction(deps.branch(), env.clone(), sender.clone(), tokens, params)?;
+
+            resp = resp
+                .add_submessages(listed_hook_msgs(deps.storage, &auction)?)
+                .add_attribute("nft_id", entry.nft_id)
+                .add_event(AuctionEvent::listed(&auction).into());
+        }
+
+        Ok(resp)
+    }
+
+    //We check that the house holds every token and that none of them is already auctioned before storing the new auction.
+    fn open_auction(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        tokens: Vec<LotToken>,
+        params: AuctionParams,
+    ) -> Result<Auction, ContractError> {
+        check_not_paused(deps.storage, Operation::Creation)?;
+
+        if tokens.is_empty() {
+            return Err(ContractError::EmptyLot);
+        }
+
+        for (position, token) in tokens.iter().enumerate() {
+            if tokens[..position].contains(token) {
+                return Err(ContractError::DuplicateToken);
+            }
+
+            let query_msg: Cw721QueryMsg = Cw721QueryMsg::OwnerOf {
+                token_id: token.nft_id.clone(),
+                include_expired: None,
+            };
+
+            let query_response: OwnerOfResponse =
+                deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: token.nft_contract.clone(),
+                    msg: to_binary(&query_msg)?,
+                }))?;
+
+            if query_response.owner != env.contract.address {
+                return Err(ContractError::NoNFT);
+            }
+
+            //Holding the token is not enough, anyone could list a token transferred to the house without a deposit
+            let depositor = DEPOSITS.may_load(deps.storage, (&token.nft_contract, &token.nft_id))?;
+
+            if depositor.as_ref() != Some(&sender) {
+                return Err(ContractError::NotDepositor);
+            }
+        }
+
+        if auctions()
+            .may_load(deps.storage, &tokens[0].nft_id)?
+            .is_some()
+        {
+            return Err(ContractError::AuctionExists);
+        }
+
+        for token in tokens.iter() {
+            if ESCROWED_TOKENS.has(deps.storage, (&token.nft_contract, &token.nft_id)) {
+                return Err(ContractError::AuctionExists);
+            }
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+
+        if !config.accepted_denoms.contains(&params.denom) {
+            return Err(ContractError::DenomNotAccepted(params.denom));
+        }
+
+        //Without an explicit duration the auction lasts 72h, kept within the bounds set by the owners.
+        let duration = match params.duration {
+            Some(duration) => {
+                if duration < config.min_duration || duration > config.max_duration {
+                    return Err(ContractError::InvalidDuration);
+                }
+
+                duration
+            }
+            None => DEFAULT_DURATION.clamp(config.min_duration, config.max_duration),
+        };
+
+        //Scheduled auctions are listed right away but their duration only counts from the start time.
+        //The start time is bounded like the duration, so the upcoming auctions can't pile up years ahead.
+        let start = match params.start_time {
+            Some(start_time)
+                if start_time <= env.block.time
+                    || start_time > env.block.time.plus_seconds(config.max_duration) =>
+            {
+                return Err(ContractError::InvalidStartTime)
+            }
+            Some(start_time) => start_time,
+            None => env.block.time,
+        };
+
+        let gate = params
+            .gate
+            .map(|gate| validate_gate(deps.as_ref(), gate))
+            .transpose()?;
+
+        let mut tokens = tokens.into_iter();
+        let first = tokens.next().unwrap();
+
+        let new_auction = Auction {
+            nft_id: first.nft_id,
+            nft_contract: first.nft_contract,
+            lot: tokens.collect(),
+            current_bid: None,
+            current_bidder: None,
+            min_bid: coin(params.min_bid.into(), params.denom.clone()),
+            buyout_price: Some(coin(params.buyout.into(), params.denom)),
+            owner: sender,
+            end_auction: start.plus_seconds(duration),
+            start_time: params.start_time,
+            gate,
+            bid_history: vec![],
+            edited_at: None,
+            flag: None,
+        };
+
+        //Store the new auction in the contract state, its tokens are escrowed rather than deposited from now on
+        auctions().save(deps.storage, &new_auction.nft_id, &new_auction)?;
+        escrow_tokens(deps.storage, &new_auction)?;
+
+        for token in new_auction.tokens() {
+            DEPOSITS.remove(deps.storage, (&token.nft_contract, &token.nft_id));
+        }
+
+        Ok(new_auction)
+    }
+
+    //Messages paying a sale to the seller, minus the house fee that is sent to the fee recipient.
+    fn payout_msgs(config: &Config, seller: &str, price: Coin) -> Vec<BankMsg> {
+        let fee = price.amount.multiply_ratio(config.fee_bps, MAX_FEE_BPS);
+        let proceeds = price.amount - fee;
+
+        let mut msgs = vec![];
+
+        if !fee.is_zero() {
+            msgs.push(BankMsg::Send {
+                to_address: config.fee_recipient.to_string(),
+                amount: vec![coin(fee.u128(), price.denom.clone())],
+            });
+        }
+
+        if !proceeds.is_zero() {
+            msgs.push(BankMsg::Send {
+                to_address: seller.to_string(),
+                amount: vec![coin(proceeds.u128(), price.denom)],
+            });
+        }
+
+        msgs
+    }
+
+    fn validate_gate(deps: Deps<ArchwayQuery>, gate: BidderGate) -> Result<Gate, ContractError> {
+        let gate = match gate {
+            BidderGate::Allowlist { addresses } => {
+                if addresses.is_empty() {
+                    return Err(ContractError::InvalidGate);
+                }
+
+                let addresses = addresses
+                    .iter()
+                    .map(|address| deps.api.addr_validate(address))
+                    .collect::<StdResult<_>>()?;
+
+                Gate::Allowlist { addresses }
+            }
+            BidderGate::MerkleRoot { root } => {
+                let root = decode_hash(&root).ok_or(ContractError::InvalidGate)?;
+
+                Gate::MerkleRoot {
+                    root: root.to_vec().into(),
+                }
+            }
+            BidderGate::Holders { collection } => Gate::Holders {
+                collection: deps.api.addr_validate(&collection)?,
+            },
+        };
+
+        Ok(gate)
+    }
+
+    //Holders are checked with a cw721 `Tokens` query, owning a single token of the collection is enough.
+    fn check_gate(
+        deps: Deps<ArchwayQuery>,
+        auction: &Auction,
+        sender: &Addr,
+        proof: Option<Vec<String>>,
+    ) -> Result<(), ContractError> {
+        let allowed = match &auction.gate {
+            None => true,
+            Some(Gate::Allowlist { addresses }) => addresses.contains(sender),
+            Some(Gate::MerkleRoot { root }) => {
+                verify_merkle_proof(root, sender, &proof.unwrap_or_default())?
+            }
+            Some(Gate::Holders { collection }) => {
+                let query_msg: Cw721QueryMsg = Cw721QueryMsg::Tokens {
+                    owner: sender.to_string(),
+                    start_after: None,
+                    limit: Some(1),
+                };
+
+                let tokens: TokensResponse =
+                    deps.querier.query_wasm_smart(collection, &query_msg)?;
+
+                !tokens.tokens.is_empty()
+            }
+        };
+
+        if !allowed {
+            return Err(ContractError::NotAllowedToBid);
+        }
+
+        Ok(())
+    }
+
+    //The leaf is the sha256 of the address, each proof element is hashed with the current node in sorted order.
+    fn verify_merkle_proof(
+        root: &Binary,
+        sender: &Addr,
+        proof: &[String],
+    ) -> Result<bool, ContractError> {
+        let leaf: [u8; 32] = Sha256::digest(sender.as_bytes()).into();
+
+        let computed = proof.iter().try_fold(leaf, |node, sibling| {
+            let sibling = decode_hash(sibling).ok_or(ContractError::InvalidGate)?;
+
+            let (first, second) = if node <= sibling {
+                (node, sibling)
+            } else {
+                (sibling, node)
+            };
+
+            let parent: [u8; 32] = Sha256::new()
+                .chain_update(first)
+                .chain_update(second)
+                .finalize()
+                .into();
+
+            Ok::<_, ContractError>(parent)
+        })?;
+
+        Ok(computed.as_slice() == root.as_slice())
+    }
+
+    fn decode_hash(hash: &str) -> Option<[u8; 32]> {
+        hex::decode(hash).ok()?.try_into().ok()
+    }
+
+    //Smallest bid beating `amount`
+    fn next_bid(config: &Config, amount: u128) -> u128 {
+        let increment = Uint128::from(amount).multiply_ratio(config.min_increment_bps, MAX_FEE_BPS);
+
+        amount + increment.u128().max(1)
+    }
+
+    //Message giving an escrowed bid back to its bidder
+    fn refund_msg(bid: &BidRecord) -> BankMsg {
+        BankMsg::Send {
+            to_address: bid.bidder.to_string(),
+            amount: vec![bid.amount.clone()],
+        }
+    }
+
+    //Messages moving every token of an auction to the recipient. They are all part of the same transaction so the lot is
+    //either transferred entirely or not at all.
+    fn transfer_lot_msgs(auction: &Auction, recipient: &str) -> StdResult<Vec<WasmMsg>> {
+        auction
+            .tokens()
+            .into_iter()
+            .map(|token| {
+                let send_nft_msg = Cw721ExecuteMsg::TransferNft {
+                    recipient: recipient.to_string(),
+                    token_id: token.nft_id,
+                };
+
+                Ok(WasmMsg::Execute {
+                    contract_addr: token.nft_contract,
+                    msg: to_binary(&send_nft_msg)?,
+                    funds: vec![],
+                })
+            })
+            .collect()
+    }
+
+    //Moves an auction that just ended out of the open auctions and into the archive.
+    fn archive_auction(
+        storage: &mut dyn Storage,
+        auction: Auction,
+        outcome: Outcome,
+        ended_at: Timestamp,
+    ) -> Result<u64, ContractError> {
+        auctions().remove(storage, &auction.nft_id)?;
+        FAILED_SETTLEMENTS.remove(storage, &auction.nft_id);
+
+        let bidders = AUCTION_ACTIVITY
+            .prefix(&auction.nft_id)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for bidder in bidders {
+            AUCTION_ACTIVITY.remove(storage, (&auction.nft_id, &bidder));
+        }
+
+        for token in auction.tokens() {
+            ESCROWED_TOKENS.remove(storage, (&token.nft_contract, &token.nft_id));
+        }
+
+        let id = ARCHIVE_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+        ARCHIVE_COUNT.save(storage, &id)?;
+
+        let archived = ArchivedAuction {
+            id,
+            auction,
+            outcome,
+            ended_at,
+        };
+
+        ARCHIVE.save(storage, id, &archived)?;
+
+        Ok(id)
+    }
+
+    //With `proxy`, the funds sent are the most the bidder is willing to pay. The contract only bids what is needed to beat
+    //the other bids and raises it when someone bids again, up to that ceiling. What is left is refunded at settlement.
+    pub fn bid(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+        funds: Vec<Coin>,
+        nft_id: String,
+        blocktime: u64,
+        proxy: bool,
+        proof: Option<Vec<String>>,
+    ) -> ArchwayResult<ContractError> {
+        check_not_paused(deps.storage, Operation::Bidding)?;
+
+        //We check if the auction we want to bid on exists
+        let mut auction = auctions()
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::NoAuction)?;
+
+        //Sellers can't raise the price of their own auction or farm activity points on it
+        if sender == auction.owner {
+            return Err(ContractError::OwnAuction);
+        }
+
+        check_gate(deps.as_ref(), &auction, &sender, proof)?;
+
+        //We check if the auction is started and not finished.
+        if !auction.has_started(blocktime) {
+            return Err(ContractError::NotStarted);
+        }
+
+        if blocktime > auction.end_auction.seconds() {
+            return Err(ContractError::AuctionFinished)
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+
+        let auction_denom = auction.min_bid.denom.clone();
+        //We check if the bidder sent the funds wanted by the auction creator (and that they correspond to the right denom)
+        let new_bid_amount = funds
+            .iter()
+            .find(|coin| coin.denom == auction_denom)
+            .ok_or(ContractError::NoFunds)?
+            .amount
+            .u128();
+
+        if new_bid_amount < auction.min_bid.amount.u128() {
+            return Err(ContractError::BidUnderMinimum);
+        }
+
+        //If there is already a bid, ours must beat it by the minimum increment.
+        if let Some(current_bid) = auction.current_bid.as_ref() {
+            if new_bid_amount < next_bid(&config, current_bid.amount.u128()) {
+                return Err(ContractError::BidNotEnough);
+            }
+        }
+
+        let new_bid = BidRecord {
+            bidder: sender.clone(),
+            amount: coin(new_bid_amount, auction_denom.clone()),
+            placed_at: Timestamp::from_seconds(blocktime),
+            proxy,
+        };
+
+        //Bids of auctions opened before the bid history was introduced are tracked from now on
+        auction.bid_history = auction.escrowed_bids();
+
+        let mut resp = Response::new().add_attribute("bidder", sender.clone());
+        let mut placed = vec![];
+
+        let (leader, price) = match auction.leading_bid() {
+            //A proxy bid with a ceiling at least as high keeps the lead, the new bid is outbid and refunded right away.
+            Some(leading)
+                if leading.bidder != sender && leading.amount.amount.u128() >= new_bid_amount =>
+            {
+                let outbid = AuctionEvent::Outbid {
+                    auction_id: auction.nft_id.clone(),
+                    collection: auction.nft_contract.clone(),
+                    bidder: sender.clone(),
+                    amount: new_bid.amount.clone(),
+                    outbid_at: new_bid.placed_at,
+                };
+
+                resp = resp
+                    .add_message(refund_msg(&new_bid))
+                    .add_attribute("method", "outbid_by_proxy")
+                    .add_event(outbid.into());
+
+                placed.push((sender.clone(), new_bid.amount.clone()));
+
+                let price = next_bid(&config, new_bid_amount).min(leading.amount.amount.u128());
+
+                (leading.bidder, price)
+            }
+            //Otherwise we take the lead. The previous bid stays escrowed, unless it is the sender raising his own bid.
+            leading => {
+                resp = resp.add_attribute("method", if proxy { "proxy_bid" } else { "bid" });
+
+                let price = match (&leading, proxy) {
+                    (_, false) => new_bid_amount,
+                    (None, true) => auction.min_bid.amount.u128(),
+                    //A proxy raising its own ceiling keeps its price
+                    (Some(leading), true) if leading.bidder == sender => auction
+                        .current_bid
+                        .as_ref()
+                        .map_or(new_bid_amount, |bid| bid.amount.u128()),
+                    (Some(leading), true) => next_bid(&config, leading.amount.amount.u128())
+                        .max(auction.min_bid.amount.u128())
+                        .min(new_bid_amount),
+                };
+
+                if let Some(leading) = leading {
+                    if leading.bidder == sender {
+                        resp = resp.add_message(refund_msg(&leading));
+                        auction.bid_history.pop();
+                    } else {
+                        let outbid = AuctionEvent::Outbid {
+                            auction_id: auction.nft_id.clone(),
+                            collection: auction.nft_contract.clone(),
+                            bidder: leading.bidder.clone(),
+                            amount: auction.current_bid.clone().unwrap_or(leading.amount),
+                            outbid_at: new_bid.placed_at,
+                        };
+
+                        resp = resp
+                            .add_attribute("old_bidder", leading.bidder)
+                            .add_event(outbid.into());
+                    }
+                }
+
+                auction.bid_history.push(new_bid);
+
+                (sender.clone(), price)
+            }
+        };
+
+        //Only the latest bids stay escrowed, the oldest outbid ones are refunded
+        let overflow = auction.bid_history.len().saturating_sub(MAX_BID_HISTORY);
+        for outbid in auction.bid_history.drain(..overflow) {
+            resp = resp.add_message(refund_msg(&outbid));
+        }
+
+        let price = coin(price, auction_denom);
+
+        //The leading bid is placed at the price it needs, a proxy bid may just have been raised
+        placed.push((leader.clone(), price.clone()));
+
+        for (bidder, amount) in placed {
+            let bid_placed = AuctionEvent::BidPlaced {
+                auction_id: auction.nft_id.clone(),
+                collection: auction.nft_contract.clone(),
+                bidder: bidder.clone(),
+                amount: amount.clone(),
+                end_time: auction.end_auction,
+            };
+
+            let bid_hook = AuctionHookMsg::Bid {
+                auction_id: auction.nft_id.clone(),
+                bidder,
+                amount,
+            };
+
+            resp = resp
+                .add_submessages(hook_msgs(deps.storage, bid_hook)?)
+                .add_event(bid_placed.into());
+        }
+
+        AUCTION_ACTIVITY.update(deps.storage, (&nft_id, &sender), |points| -> StdResult<_> {
+            Ok(points.unwrap_or_default() + ACTIVITY_POINTS)
+        })?;
+
+        //We update the new current highest offer in the contract state.
+        auction.current_bidder = Some(leader);
+        auction.current_bid = Some(price);
+
+        auctions().save(deps.storage, &nft_id, &auction)?;
+
+        Ok(resp)
+    }
+
+    //Outbid bidders can take their escrowed bids back at any time. The current bidder can only take his bid back before
+    //the end if the seller edited the auction since it was placed, the latest outbid bid then leads again. Retracting is
+    //a refund, it stays possible while the house is paused.
+    pub fn retract_bid(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+        nft_id: String,
+        blocktime: u64,
+    ) -> ArchwayResult<ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        let mut auction = auctions()
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::NoAuction)?;
+
+        let leading = auction.leading_bid().filter(|bid| bid.bidder == sender);
+
+        if let Some(leading) = leading.as_ref() {
+            if blocktime > auction.end_auction.seconds() {
+                return Err(ContractError::AuctionFinished);
+            }
+
+            let edited = auction
+                .edited_at
+                .map_or(false, |edited_at| edited_at > leading.placed_at);
+
+            if !edited {
+                return Err(ContractError::RetractionNotAllowed);
+            }
+        }
+
+        let (retracted, kept): (Vec<_>, Vec<_>) = auction
+            .escrowed_bids()
+            .into_iter()
+            .partition(|bid| bid.bidder == sender);
+
+        if retracted.is_empty() {
+            return Err(ContractError::NoBidToRetract);
+        }
+
+        auction.bid_history = kept;
+
+        //The latest outbid bid leads again, a proxy bid at the price it needs to beat the bid before it
+        if leading.is_some() {
+            let restored = auction.bid_history.last().map(|bid| {
+                let price = match auction.bid_history.iter().rev().nth(1) {
+                    _ if !bid.proxy => bid.amount.amount.u128(),
+                    Some(previous) => next_bid(&config, previous.amount.amount.u128())
+                        .max(auction.min_bid.amount.u128())
+                        .min(bid.amount.amount.u128()),
+                    None => auction.min_bid.amount.u128(),
+                };
+
+                (bid.bidder.clone(), coin(price, &bid.amount.denom))
+            });
+
+            auction.current_bidder = restored.as_ref().map(|(bidder, _)| bidder.clone());
+            auction.current_bid = restored.map(|(_, price)| price);
+        }
+
+        auctions().save(deps.storage, &nft_id, &auction)?;
+
+        let refund = coin(
+            retracted.iter().map(|bid| bid.amount.amount.u128()).sum(),
+            &auction.min_bid.denom,
+        );
+
+        let retracted_event = AuctionEvent::BidRetracted {
+            auction_id: auction.nft_id.clone(),
+            collection: auction.nft_contract.clone(),
+            bidder: sender.clone(),
+            refund: refund.clone(),
+            current_bidder: auction.current_bidder.clone(),
+            current_bid: auction.current_bid.clone(),
+        };
+
+        let resp = Response::new()
+            .add_message(BankMsg::Send {
+                to_address: sender.to_string(),
+                amount: vec![refund.clone()],
+            })
+            .add_attribute("method", "retract_bid")
+            .add_attribute("nft_id", nft_id)
+            .add_attribute("bidder", sender)
+            .add_attribute("refund", refund.to_string())
+            .add_event(retracted_event.into());
+
+        Ok(resp)
+    }
+
+    //Every edit is timestamped, which lets the current bidder retract his bid. Once there are bids, only changes favourable
+    //to the buyers are accepted.
+    pub fn update_auction(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        nft_id: String,
+        update: AuctionUpdate,
+    ) -> ArchwayResult<ContractError> {
+        check_not_paused(deps.storage, Operation::Creation)?;
+
+        let mut auction = auctions()
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::NoAuction)?;
+
+        if sender != auction.owner {
+            return Err(ContractError::Unauthorized);
+        }
+
+        if env.block.time > auction.end_auction {
+            return Err(ContractError::AuctionFinished);
+        }
+
+        let AuctionUpdate {
+            min_bid,
+            buyout,
+            remove_buyout,
+            extend_by,
+        } = update;
+
+        if min_bid.is_none() && buyout.is_none() && !remove_buyout && extend_by.is_none() {
+            return Err(ContractError::InvalidAuctionUpdate);
+        }
+
+        let has_bids = auction.current_bid.is_some();
+        let denom = auction.min_bid.denom.clone();
+
+        if let Some(min_bid) = min_bid {
+            if min_bid as u128 >= auction.min_bid.amount.u128() {
+                return Err(ContractError::InvalidAuctionUpdate);
+            }
+
+            auction.min_bid = coin(min_bid.into(), denom.clone());
+        }
+
+        match (buyout, remove_buyout) {
+            (Some(_), true) => return Err(ContractError::InvalidAuctionUpdate),
+            (Some(buyout), false) => {
+                if has_bids {
+                    let lowered = auction
+                        .buyout_price
+                        .as_ref()
+                        .map_or(false, |price| (buyout as u128) < price.amount.u128());
+
+                    let above_bid = auction
+                        .current_bid
+                        .as_ref()
+                        .map_or(true, |bid| (buyout as u128) > bid.amount.u128());
+
+                    if !lowered || !above_bid {
+                        return Err(ContractError::EditNotAllowed);
+                    }
+                }
+
+                auction.buyout_price = Some(coin(buyout.into(), denom));
+            }
+            (None, true) => {
+                if has_bids {
+                    return Err(ContractError::EditNotAllowed);
+                }
+
+                auction.buyout_price = None;
+            }
+            (None, false) => {}
+        }
+
+        if let Some(extend_by) = extend_by {
+            if has_bids {
+                return Err(ContractError::EditNotAllowed);
+            }
+
+            let config = CONFIG.load(deps.storage)?;
+            let end_auction = auction.end_auction.plus_seconds(extend_by);
+
+            //Scheduled auctions are bounded from their start time
+            let start = auction.start_time.unwrap_or(env.block.time).max(env.block.time);
+
+            if end_auction.seconds() - start.seconds() > config.max_duration {
+                return Err(ContractError::InvalidDuration);
+            }
+
+            auction.end_auction = end_auction;
+        }
+
+        auction.edited_at = Some(env.block.time);
+
+        auctions().save(deps.storage, &nft_id, &auction)?;
+
+        let edited = AuctionEvent::Edited {
+            auction_id: auction.nft_id.clone(),
+            collection: auction.nft_contract.clone(),
+            seller: auction.owner.clone(),
+            min_bid: auction.min_bid.clone(),
+            buyout_price: auction.buyout_price.clone(),
+            end_time: auction.end_auction,
+        };
+
+        let resp = Response::new()
+            .add_attribute("method", "update_auction")
+            .add_attribute("nft_id", nft_id)
+            .add_event(edited.into());
+
+        Ok(resp)
+    }
+
+    pub fn buyout(
+        deps: DepsMut<ArchwayQuery>,
+        sender: Addr,
+        funds: Vec<Coin>,
+        nft_id: String,
+        blocktime: u64,
+        proof: Option<Vec<String>>,
+    ) -> ArchwayResult<ContractError> {
+        check_not_paused(deps.storage, Operation::Buyout)?;
+
+        //We check if the auction we want to buyout exists
+        let auction = auctions()
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::NoAuction)?;
+
+        //Sellers can't raise the price of their own auction or farm activity points on it
+        if sender == auction.owner {
+            return Err(ContractError::OwnAuction);
+        }
+
+        check_gate(deps.as_ref(), &auction, &sender, proof)?;
+
+        //We check if the auction is started and not finished.
+        if !auction.has_started(blocktime) {
+            return Err(ContractError::NotStarted);
+        }
+
+        if blocktime > auction.end_auction.seconds() {
+            return Err(ContractError::AuctionFinished)
+        }
+
+        //We check if the buyer sent the funds wanted by the auction creator (and that they correspond to the right denom)
+        let buyout_amount = funds
+            .iter()
+            .find(|coin| coin.denom == auction.min_bid.denom)
+            .ok_or(ContractError::NoFunds)?
+            .amount
+            .u128();
+
+        //The seller may have removed the buyout price
+        let buyout_price = auction.buyout_price.clone().ok_or(ContractError::NoBuyout)?;
+
+        if buyout_amount < buyout_price.amount.u128() {
+            return Err(ContractError::PriceNotMet);
+        }
+
+        //We check if there is already a bidder. If that's the case, every escrowed bid is sent back since it lost.
+        let mut resp;
+
+        if let (Some(leading), Some(old_bid)) = (auction.leading_bid(), auction.current_bid.clone())
+        {
+            let old_bidder = leading.bidder.clone();
+
+            let outbid = AuctionEvent::Outbid {
+                auction_id: auction.nft_id.clone(),
+                collection: auction.nft_contract.clone(),
+                bidder: old_bidder.clone(),
+                amount: old_bid,
+                outbid_at: Timestamp::from_seconds(blocktime),
+            };
+
+            resp = Response::new()
+                .add_messages(auction.escrowed_bids().iter().map(refund_msg))
+                .add_attribute("method", "buyout_with_refund")
+                .add_attribute("buyer", sender.clone())
+                .add_attribute("old_bidder", old_bidder)
+                .add_event(outbid.into());
+        } else {
+            resp = Response::new()
+                .add_attribute("method", "buyout")
+                .add_attribute("buyer", sender.clone());
+        }
+
+        // We prepare the messages to send the NFTs to the buyer
+
+        let send_nft_msgs = transfer_lot_msgs(&auction, sender.as_str())?;
+
+        resp = resp.add_messages(send_nft_msgs);
+
+        //The seller is paid the buyout price and the buyer gets back what he sent on top of it.
+        let config = CONFIG.load(deps.storage)?;
+
+        let send_funds_msgs =
+            payout_msgs(&config, auction.owner.as_str(), buyout_price.clone());
+
+        resp = resp.add_messages(send_funds_msgs);
+
+        let excess = buyout_amount - buyout_price.amount.u128();
+
+        if excess > 0 {
+            resp = resp.add_message(BankMsg::Send {
+                to_address: sender.to_string(),
+                amount: vec![coin(excess, buyout_price.denom.clone())],
+            });
+        }
+
+        credit_activity(deps.storage, &auction, &sender)?;
+
+        let bought_out = AuctionEvent::BoughtOut {
+            auction_id: auction.nft_id.clone(),
+            collection: auction.nft_contract.clone(),
+            seller: auction.owner.clone(),
+            buyer: sender.clone(),
+            price: buyout_price.clone(),
+            bought_at: Timestamp::from_seconds(blocktime),
+        };
+
+        let settled_hook = AuctionHookMsg::Settled {
+            auction_id: auction.nft_id.clone(),
+            seller: auction.owner.clone(),
+            winner: Some(sender.clone()),
+            price: Some(buyout_price.clone()),
+        };
+
+        resp = resp
+            .add_submessages(hook_msgs(deps.storage, settled_hook)?)
+            .add_event(bought_out.into());
+
+        //We move the auction from the open auctions to the archive
+
+        let outcome = Outcome::Sold {
+            buyer: sender,
+            price: buyout_price,
+        };
+
+        archive_auction(deps.storage, auction, outcome, Timestamp::from_seconds(blocktime))?;
+
+        Ok(resp)
+    }
+
+    pub fn close(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        blocktime: u64,
+        nft_id: String,
+    ) -> ArchwayResult<ContractError> {
+        //We check if the auction we want to close exists
+        let auction = auctions()
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::NoAuction)?;
+
+        let method;
+
+        if blocktime > auction.end_auction.seconds() {
+            //If auction is finished, the auction creator, the winner or the house itself (see `settle_expired`) can close it.
+            if sender != auction.owner
+                && Some(&sender) != auction.current_bidder.as_ref()
+                && sender != env.contract.address
+            {
+                return Err(ContractError::CannotClose)
+            }
+
+            method = if auction.current_bid.is_some() {
+                "close_completed_auction"
+            } else {
+                "close_without_bids"
+            };
+        } else {
+            //Before the end, only the owner can close the auction: he takes the NFT back if there are no bids
+            //or accepts the current bid without waiting for the auction to end.
+            if sender != auction.owner {
+                return Err(ContractError::Unauthorized);
+            }
+
+            method = if auction.current_bid.is_some() {
+                "accept_current_bid"
+            } else {
+                "close_without_bids"
+            };
+        }
+
+        let resp = settle(deps, env, auction)?.add_attribute("method", method);
+
+        Ok(resp)
+    }
+
+    //Settling sends the lot to the winner and the winning bid to the auction creator, or gives the lot back to its creator
+    //if nobody bid. The auction is moved to the archive. Returning a lot without bids is possible while settlement is paused.
+    fn settle(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        auction: Auction,
+    ) -> ArchwayResult<ContractError> {
+        if auction.current_bid.is_some() {
+            check_not_paused(deps.storage, Operation::Settlement)?;
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+
+        let (resp, outcome) = match (auction.current_bidder.clone(), auction.current_bid.clone()) {
+            (Some(winner), Some(winning_bid)) => {
+                let send_nft_msgs = transfer_lot_msgs(&auction, winner.as_str())?;
+
+                let send_funds_msgs =
+                    payout_msgs(&config, auction.owner.as_str(), winning_bid.clone());
+
+                let mut resp = Response::new()
+                    .add_messages(send_nft_msgs)
+                    .add_messages(send_funds_msgs)
+                    .add_attribute("nft_receiver", winner.clone());
+
+                //Outbid bids are refunded, as well as the part of a winning proxy bid above the price reached
+                let mut escrowed = auction.escrowed_bids();
+
+                if let Some(leading) = escrowed.last_mut() {
+                    leading.amount.amount -= winning_bid.amount;
+                }
+
+                for refund in escrowed.iter().filter(|bid| !bid.amount.amount.is_zero()) {
+                    resp = resp.add_message(refund_msg(refund));
+                }
+
+                credit_activity(deps.storage, &auction, &winner)?;
+
+                let outcome = Outcome::Sold {
+                    buyer: winner,
+                    price: winning_bid,
+                };
+
+                (resp, outcome)
+            }
+            _ => {
+                let send_nft_msgs = transfer_lot_msgs(&auction, auction.owner.as_str())?;
+
+                let resp = Response::new()
+                    .add_messages(send_nft_msgs)
+                    .add_attribute("nft_receiver", auction.owner.clone());
+
+                (resp, Outcome::Returned {})
+            }
+        };
+
+        let (winner, price) = match &outcome {
+            Outcome::Sold { buyer, price } => (Some(buyer.clone()), Some(price.clone())),
+            _ => (None, None),
+        };
+
+        let settled_hook = AuctionHookMsg::Settled {
+            auction_id: auction.nft_id.clone(),
+            seller: auction.owner.clone(),
+            winner: winner.clone(),
+            price: price.clone(),
+        };
+
+        let settled = AuctionEvent::Settled {
+            auction_id: auction.nft_id.clone(),
+            collection: auction.nft_contract.clone(),
+            seller: auction.owner.clone(),
+            winner,
+            price,
+            settled_at: env.block.time,
+        };
+
+        let resp = resp
+            .add_submessages(hook_msgs(deps.storage, settled_hook)?)
+            .add_event(settled.into());
+
+        archive_auction(deps.storage, auction, outcome, env.block.time)?;
+
+        Ok(resp)
+    }
+
+    //Moderators can delist an auction at any time, even while the house is paused. The bidders are refunded and the lot goes
+    //back to its creator. Sending it to anyone else, for example the rightful holder of a stolen NFT, takes a proposal.
+    pub fn force_cancel(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        nft_id: String,
+        reason: String,
+    ) -> ArchwayResult<ContractError> {
+        check_role(deps.storage, &sender, Role::Moderator)?;
+
+        let auction = auctions()
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::NoAuction)?;
+
+        let seller = auction.owner.clone();
+
+        cancel_auction(deps, env, sender, auction, reason, seller)
+    }
+
+    //Delists an auction, refunding its bidders and sending its lot to `nft_receiver`.
+    fn cancel_auction(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        auction: Auction,
+        reason: String,
+        nft_receiver: Addr,
+    ) -> ArchwayResult<ContractError> {
+        let mut resp = Response::new()
+            .add_messages(transfer_lot_msgs(&auction, nft_receiver.as_str())?)
+            .add_attribute("method", "force_cancel")
+            .add_attribute("nft_id", auction.nft_id.clone())
+            .add_attribute("reason", reason.clone())
+            .add_attribute("nft_receiver", nft_receiver.clone());
+
+        for escrowed in auction.escrowed_bids() {
+            resp = resp
+                .add_message(refund_msg(&escrowed))
+                .add_attribute("refunded_bidder", escrowed.bidder);
+        }
+
+        let cancelled = AuctionEvent::Cancelled {
+            auction_id: auction.nft_id.clone(),
+            collection: auction.nft_contract.clone(),
+            seller: auction.owner.clone(),
+            moderator: sender.clone(),
+            reason: reason.clone(),
+            nft_receiver: nft_receiver.clone(),
+        };
+
+        let cancelled_hook = AuctionHookMsg::Cancelled {
+            auction_id: auction.nft_id.clone(),
+            reason: reason.clone(),
+        };
+
+        resp = resp
+            .add_submessages(hook_msgs(deps.storage, cancelled_hook)?)
+            .add_event(cancelled.into());
+
+        let outcome = Outcome::Moderated {
+            moderator: sender,
+            reason,
+            nft_receiver,
+        };
+
+        let archive_id = archive_auction(deps.storage, auction, outcome, env.block.time)?;
+
+        Ok(resp.add_attribute("archive_id", archive_id.to_string()))
+    }
+
+    //Moderators can drop an auction whose settlement failed, when its lot can't be transferred anymore. The bidders are
+    //refunded and the lot stays in the house, which is why only recorded failures can be dropped.
+    pub fn drop_auction(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        nft_id: String,
+    ) -> ArchwayResult<ContractError> {
+        check_role(deps.storage, &sender, Role::Moderator)?;
+
+        let auction = auctions()
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::NoAuction)?;
+
+        let reason = FAILED_SETTLEMENTS
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::SettlementNotFailed)?;
+
+        let mut resp = Response::new()
+            .add_attribute("method", "drop_auction")
+            .add_attribute("nft_id", nft_id)
+            .add_attribute("reason", reason.clone());
+
+        for escrowed in auction.escrowed_bids() {
+            resp = resp
+                .add_message(refund_msg(&escrowed))
+                .add_attribute("refunded_bidder", escrowed.bidder);
+        }
+
+        let dropped = AuctionEvent::Dropped {
+            auction_id: auction.nft_id.clone(),
+            collection: auction.nft_contract.clone(),
+            seller: auction.owner.clone(),
+            moderator: sender.clone(),
+            reason: reason.clone(),
+        };
+
+        let cancelled_hook = AuctionHookMsg::Cancelled {
+            auction_id: auction.nft_id.clone(),
+            reason: reason.clone(),
+        };
+
+        resp = resp
+            .add_submessages(hook_msgs(deps.storage, cancelled_hook)?)
+            .add_event(dropped.into());
+
+        let outcome = Outcome::Dropped {
+            moderator: sender,
+            reason,
+        };
+
+        let archive_id = archive_auction(deps.storage, auction, outcome, env.block.time)?;
+
+        Ok(resp.add_attribute("archive_id", archive_id.to_string()))
+    }
+
+    //Moderators can flag an auction at any time, even while the house is paused. A `None` reason removes the flag.
+    pub fn flag_auction(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        sender: Addr,
+        nft_id: String,
+        reason: Option<String>,
+    ) -> ArchwayResult<ContractError> {
+        check_role(deps.storage, &sender, Role::Moderator)?;
+
+        let mut auction = auctions()
+            .may_load(deps.storage, &nft_id)?
+            .ok_or(ContractError::NoAuction)?;
+
+        auction.flag = reason.clone().map(|reason| Flag {
+            moderator: sender.clone(),
+            reason,
+            flagged_at: env.block.time,
+        });
+
+        auctions().save(deps.storage, &nft_id, &auction)?;
+
+        let flagged = AuctionEvent::Flagged {
+            auction_id: auction.nft_id,
+            collection: auction.nft_contract,
+            moderator: sender.clone(),
+            reason: reason.clone(),
+        };
+
+        let method = if reason.is_some() {
+            "flag_auction"
+        } else {
+            "unflag_auction"
+        };
+
+        let resp = Response::new()
+            .add_attribute("method", method)
+            .add_attribute("nft_id", nft_id)
+            .add_attribute("moderator", sender)
+            .add_event(flagged.into());
+
+        Ok(resp)
+    }
+
+    //Anyone (typically a keeper bot) can settle up to `limit` expired auctions, oldest end time first. Each settlement runs as a
+    //`Close` sub message sent by the house itself, so one that fails (for example a cw721 contract rejecting the transfer) is
+    //reverted and reported in the reply without blocking the others. Failed settlements are recorded and skipped by later
+    //calls, so they can't hold the keeper back.
+    pub fn settle_expired(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        limit: u32,
+    ) -> ArchwayResult<ContractError> {
+        let limit = limit.min(MAX_SETTLE_LIMIT) as usize;
+
+        //Sales can't settle while settlement is paused, they are left for later instead of being recorded as failed
+        let settlement_paused = check_not_paused(deps.storage, Operation::Settlement).is_err();
+
+        let expired = auctions()
+            .idx
+            .end_auction
+            .prefix_range(
+                deps.storage,
+                None,
+                Some(PrefixBound::exclusive(env.block.time.seconds())),
+                Order::Ascending,
+            )
+            .filter(|item| match item {
+                Ok((nft_id, auction)) => {
+                    !FAILED_SETTLEMENTS.has(&*deps.storage, nft_id)
+                        && !(settlement_paused && auction.current_bid.is_some())
+                }
+                Err(_) => true,
+            })
+            .take(limit)
+            .map(|item| item.map(|(nft_id, _)| nft_id))
+            .collect::<StdResult<Vec<String>>>()?;
+
+        SETTLE_QUEUE.save(deps.storage, &expired)?;
+
+        let mut resp = Response::new()
+            .add_attribute("method", "settle_expired")
+            .add_attribute("expired", expired.len().to_string());
+
+        for nft_id in expired {
+            let close_msg = WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_binary(&ExecMsg::Close { nft_id })?,
+                funds: vec![],
+            };
+
+            resp = resp.add_submessage(SubMsg::reply_always(close_msg, SETTLE_REPLY_ID));
+        }
 
-    use crate::{
-        error::ContractError,
-        state::{Auction, AUCTIONS, OWNERS},
-    };
+        Ok(resp)
+    }
 
-    //Any of the owners an modify where the rewards accumulated by the contract will be sent to when they are withdrawn.
-    pub fn update_rewards_address(
+    //Anyone can place an offer on any cw721 token, listed in the house or not. The sent funds stay escrowed until the offer is accepted or cancelled.
+    pub fn place_offer(
         deps: DepsMut<ArchwayQuery>,
+        env: Env,
         sender: Addr,
-        rewards_address: Addr,
+        funds: Vec<Coin>,
+        nft_id: String,
+        nft_contract: String,
+        expires: Expiration,
     ) -> ArchwayResult<ContractError> {
-        deps.api.addr_validate(&sender.clone().into_string())?;
+        check_not_paused(deps.storage, Operation::Creation)?;
 
-        let owners = OWNERS.load(deps.storage)?;
+        let nft_contract = deps.api.addr_validate(&nft_contract)?;
 
-        if !owners.contains(&sender) {
-            return Err(ContractError::Unauthorized);
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::InvalidExpiration);
         }
 
-        let msg = ArchwayMsg::update_rewards_address(rewards_address);
+        //An offer is made in a single denom
+        if funds.len() != 1 || funds[0].amount.is_zero() {
+            return Err(ContractError::NoFunds);
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+
+        if !config.accepted_denoms.contains(&funds[0].denom) {
+            return Err(ContractError::DenomNotAccepted(funds[0].denom.clone()));
+        }
+
+        let offer_id = OFFER_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+        OFFER_COUNT.save(deps.storage, &offer_id)?;
+
+        let offer = Offer {
+            id: offer_id,
+            nft_id,
+            nft_contract: nft_contract.into_string(),
+            buyer: sender.clone(),
+            amount: funds[0].clone(),
+            expires,
+        };
+
+        offers().save(deps.storage, offer_id, &offer)?;
+
+        let placed = AuctionEvent::OfferPlaced {
+            offer_id,
+            collection: offer.nft_contract,
+            nft_id: offer.nft_id,
+            buyer: sender.clone(),
+            amount: offer.amount,
+            expires,
+        };
 
         let res = Response::new()
-            .add_message(msg)
-            .add_attribute("method", "update_rewards_address");
+            .add_attribute("method", "place_offer")
+            .add_attribute("offer_id", offer_id.to_string())
+            .add_attribute("buyer", sender)
+            .add_event(placed.into());
 
         Ok(res)
     }
 
-    //Any of the owners can withdraw the rewards to the reward address set up (This can be a wallet or ideally a contract that distributes rewards accordingly
-    //if there are multiple rewards receivers (optionally using a ratio)).
-    pub fn withdraw_rewards(
+    //The buyer can cancel his offer at any time. Once it has expired anyone can cancel it, the escrowed funds always go back to the buyer.
+    pub fn cancel_offer(
         deps: DepsMut<ArchwayQuery>,
+        env: Env,
         sender: Addr,
+        offer_id: u64,
     ) -> ArchwayResult<ContractError> {
-        deps.api.addr_validate(&sender.clone().into_string())?;
-
-        let owners = OWNERS.load(deps.storage)?;
+        let offer = offers()
+            .may_load(deps.storage, offer_id)?
+            .ok_or(ContractError::NoOffer)?;
 
-        if !owners.contains(&sender) {
-            return Err(ContractError::Unauthorized);
+        if sender != offer.buyer && !offer.expires.is_expired(&env.block) {
+            return Err(ContractError::CannotCancelOffer);
         }
 
-        let msg = ArchwayMsg::withdraw_rewards_by_limit(0);
+        offers().remove(deps.storage, offer_id)?;
+
+        let return_funds_msg = BankMsg::Send {
+            to_address: offer.buyer.to_string(),
+            amount: vec![offer.amount.clone()],
+        };
+
+        let cancelled = AuctionEvent::OfferCancelled {
+            offer_id,
+            collection: offer.nft_contract,
+            nft_id: offer.nft_id,
+            buyer: offer.buyer,
+            refund: offer.amount,
+        };
 
         let res = Response::new()
-            .add_message(msg)
-            .add_attribute("method", "withdraw_rewards");
+            .add_message(return_funds_msg)
+            .add_attribute("method", "cancel_offer")
+            .add_attribute("offer_id", offer_id.to_string())
+            .add_event(cancelled.into());
 
         Ok(res)
     }
 
-    //Any owner can add another owner that will have permissions to modify the contract metadata and be able to withdraw rewards.
-    pub fn add_owner(
+    //The depositor can take back a token he did not list, even while the house is paused.
+    pub fn withdraw_deposit(
         deps: DepsMut<ArchwayQuery>,
         sender: Addr,
-        new_owner: Addr,
+        nft_id: String,
+        nft_contract: String,
     ) -> ArchwayResult<ContractError> {
-        deps.api.addr_validate(&sender.clone().into_string())?;
-        deps.api.addr_validate(&new_owner.clone().into_string())?;
+        let depositor = DEPOSITS.may_load(deps.storage, (&nft_contract, &nft_id))?;
 
-        let mut owners = OWNERS.load(deps.storage)?;
-
-        if !owners.contains(&sender) {
-            return Err(ContractError::Unauthorized);
+        if depositor.as_ref() != Some(&sender) {
+            return Err(ContractError::NotDepositor);
         }
 
-        if !owners.contains(&new_owner.clone()) {
-            owners.push(new_owner)
-        }
+        DEPOSITS.remove(deps.storage, (&nft_contract, &nft_id));
 
-        OWNERS.save(deps.storage, &owners)?;
+        let send_nft_msg = Cw721ExecuteMsg::TransferNft {
+            recipient: sender.to_string(),
+            token_id: nft_id.clone(),
+        };
 
-        let res = Response::new().add_attribute("method", "add_owner");
+        let res = Response::new()
+            .add_message(WasmMsg::Execute {
+                contract_addr: nft_contract,
+                msg: to_binary(&send_nft_msg)?,
+                funds: vec![],
+            })
+            .add_attribute("method", "withdraw_deposit")
+            .add_attribute("nft_id", nft_id)
+            .add_attribute("depositor", sender);
 
         Ok(res)
     }
 
-    //Any owner can remove another owner to withdraw his permissions as long as he is not the last owner.
-    pub fn remove_owner(
+    //Called by a cw721 contract when a token is sent to the auction house with `SendNft`.
+    pub fn receive_nft(
         deps: DepsMut<ArchwayQuery>,
-        sender: Addr,
-        old_owner: Addr,
+        env: Env,
+        nft_contract: Addr,
+        msg: Cw721ReceiveMsg,
     ) -> ArchwayResult<ContractError> {
-        deps.api.addr_validate(&sender.clone().into_string())?;
-        deps.api.addr_validate(&old_owner.clone().into_string())?;
+        match from_binary(&msg.msg)? {
+            ReceiveMsg::CreateAuction {
+                min_bid,
+                buyout,
+                denom,
+                duration,
+                start_time,
+                gate,
+            } => {
+                let seller = deps.api.addr_validate(&msg.sender)?;
+
+                DEPOSITS.save(deps.storage, (nft_contract.as_str(), &msg.token_id), &seller)?;
+
+                let params = AuctionParams {
+                    min_bid,
+                    buyout,
+                    denom,
+                    duration,
+                    start_time,
+                    gate,
+                };
 
-        let mut owners = OWNERS.load(deps.storage)?;
+                create_auction(
+                    deps,
+                    env,
+                    seller,
+                    msg.token_id,
+                    nft_contract.into_string(),
+                    params,
+                )
+            }
+            ReceiveMsg::Deposit {} => {
+                let depositor = deps.api.addr_validate(&msg.sender)?;
 
-        if !owners.contains(&sender) {
-            return Err(ContractError::Unauthorized);
-        }
+                DEPOSITS.save(deps.storage, (nft_contract.as_str(), &msg.token_id), &depositor)?;
 
-        owners.retain(|value| value.to_string() != old_owner.to_string());
+                let res = Response::new()
+                    .add_attribute("method", "deposit")
+                    .add_attribute("nft_id", msg.token_id)
+                    .add_attribute("nft_contract", nft_contract)
+                    .add_attribute("depositor", depositor);
 
-        if owners.is_empty() {
-            return Err(ContractError::NoOwner);
+                Ok(res)
+            }
+            ReceiveMsg::AcceptOffer { offer_id } => {
+                accept_offer(deps, env, nft_contract, msg.sender, msg.token_id, offer_id)
+            }
+            ReceiveMsg::AcceptCollectionOffer { offer_id } => accept_collection_offer(
+                deps,
+                env,
+                nft_contract,
+                msg.sender,
+                msg.token_id,
+                offer_id,
+            ),
+            ReceiveMsg::AcceptBestCollectionOffer { denom } => {
+                let best = query::best_collection_offer(
+                    deps.as_ref(),
+                    env.clone(),
+                    nft_contract.to_string(),
+                    denom,
+                )?;
+                let offer = best.offer.ok_or(ContractError::NoOffer)?;
+
+                accept_collection_offer(deps, env, nft_contract, msg.sender, msg.token_id, offer.id)
+            }
         }
-
-        OWNERS.save(deps.storage, &owners)?;
-
-        let res = Response::new().add_attribute("method", "remove_owner");
-
-        Ok(res)
     }
 
-    pub fn create_auction(
+    //The token holder accepts an offer by sending the NFT to the house, which forwards it to the buyer and releases the escrowed funds to the seller.
+    pub fn accept_offer(
         deps: DepsMut<ArchwayQuery>,
         env: Env,
-        sender: Addr,
-        blocktime: u64,
+        nft_contract: Addr,
+        seller: String,
         nft_id: String,
-        nft_contract: String,
-        min_bid: u64,
-        buyout: u64,
-        denom: String,
+        offer_id: u64,
     ) -> ArchwayResult<ContractError> {
-        let query_msg: Cw721QueryMsg = Cw721QueryMsg::OwnerOf {
-            token_id: nft_id.clone(),
-            include_expired: None,
-        };
+        check_not_paused(deps.storage, Operation::Settlement)?;
 
-        let query_response: OwnerOfResponse =
-            deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr: nft_contract.clone(),
-                msg: to_binary(&query_msg)?,
-            }))?;
+        let offer = offers()
+            .may_load(deps.storage, offer_id)?
+            .ok_or(ContractError::NoOffer)?;
 
-        if query_response.owner != env.contract.address {
-            return Err(ContractError::NoNFT);
+        if offer.nft_contract != nft_contract.as_str() || offer.nft_id != nft_id {
+            return Err(ContractError::WrongNFT);
         }
 
-        let mut auctions = AUCTIONS.load(deps.storage)?;
-
-        if auctions.iter().any(|auction| auction.nft_id == nft_id) {
-            return Err(ContractError::AuctionExists);
+        if offer.expires.is_expired(&env.block) {
+            return Err(ContractError::OfferExpired);
         }
 
-        //TODO (FOR PRODUCTION): Make a list of allowed denoms to create auctions
+        offers().remove(deps.storage, offer_id)?;
 
-        let three_days = Timestamp::from_seconds(72 * 60 * 60);
-        //We create an auction with a default time limit of 72h (In the future we will make this time modifiable)
-        let new_auction = Auction {
-            nft_id,
-            nft_contract,
-            current_bid: None,
-            current_bidder: None,
-            min_bid: coin(min_bid.into(), denom.clone()),
-            buyout_price: coin(buyout.into(), denom),
-            owner: sender,
-            end_auction: three_days.plus_seconds(blocktime),
+        let send_nft_msg = Cw721ExecuteMsg::TransferNft {
+            recipient: offer.buyer.to_string(),
+            token_id: nft_id.clone(),
+        };
+
+        let wasm_send_nft = WasmMsg::Execute {
+            contract_addr: offer.nft_contract.clone(),
+            msg: to_binary(&send_nft_msg)?,
+            funds: vec![],
         };
 
-        //Store the new auction in the contract state
-        auctions.push(new_auction);
+        let config = CONFIG.load(deps.storage)?;
+
+        let send_funds_msgs = payout_msgs(&config, &seller, offer.amount.clone());
 
-        AUCTIONS.save(deps.storage, &auctions)?;
+        let accepted = AuctionEvent::OfferAccepted {
+            offer_id,
+            collection: offer.nft_contract,
+            nft_id,
+            seller: deps.api.addr_validate(&seller)?,
+            buyer: offer.buyer.clone(),
+            amount: offer.amount,
+        };
 
-        let res = Response::new().add_attribute("method", "create_auction");
+        let res = Response::new()
+            .add_message(wasm_send_nft)
+            .add_messages(send_funds_msgs)
+            .add_attribute("method", "accept_offer")
+            .add_attribute("offer_id", offer_id.to_string())
+            .add_attribute("seller", seller)
+            .add_attribute("nft_receiver", offer.buyer)
+            .add_event(accepted.into());
 
         Ok(res)
     }
 
-    pub fn bid(
+    //Anyone can bid on a number of tokens of a collection. The sent funds are split evenly between the tokens wanted and stay escrowed until filled or cancelled.
+    pub fn place_collection_offer(
         deps: DepsMut<ArchwayQuery>,
+        env: Env,
         sender: Addr,
         funds: Vec<Coin>,
-        nft_id: String,
-        blocktime: u64
+        nft_contract: String,
+        quantity: u32,
+        expires: Expiration,
     ) -> ArchwayResult<ContractError> {
-        let mut auctions = AUCTIONS.load(deps.storage)?;
-
-        //We check if the auction we want to bid on exists (get the position in the auction array)
-        let auction_position = auctions.iter().position(|auction| auction.nft_id == nft_id);
+        check_not_paused(deps.storage, Operation::Creation)?;
 
-        if auction_position.is_none() {
-            return Err(ContractError::NoAuction);
-        }
+        let nft_contract = deps.api.addr_validate(&nft_contract)?;
 
-        //We check if the auction is finished or not.
-        if blocktime > auctions[auction_position.unwrap()].end_auction.seconds() {
-            return Err(ContractError::AuctionFinished)
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::InvalidExpiration);
         }
 
-        let auction_denom = auctions[auction_position.unwrap()].min_bid.clone().denom;
-        //We check if the bidder sent the funds wanted by the auction creator (and that they correspond to the right denom)
-        if funds
-            .iter()
-            .find(|coin| coin.denom == auctions[auction_position.unwrap()].min_bid.denom)
-            == None
-        {
+        if funds.len() != 1 || funds[0].amount.is_zero() {
             return Err(ContractError::NoFunds);
         }
 
-        let new_bid_amount = funds
-            .iter()
-            .find(|coin| coin.denom == auction_denom)
-            .unwrap()
-            .amount
-            .u128();
+        let config = CONFIG.load(deps.storage)?;
 
-        if new_bid_amount < auctions[auction_position.unwrap()].min_bid.amount.into() {
-            return Err(ContractError::BidUnderMinimum);
+        if !config.accepted_denoms.contains(&funds[0].denom) {
+            return Err(ContractError::DenomNotAccepted(funds[0].denom.clone()));
         }
 
-        //We check if there is already a bidder and if our bid is higher than his. If that's the case, we update the current bidder with the new one
-        //and return the funds to the old bidder.
-        let resp;
-
-        if auctions[auction_position.unwrap()].current_bidder.is_some() {
-            //If the new bid is lower than current bid then we throw an error.
-            if new_bid_amount
-                <= auctions[auction_position.unwrap()]
-                    .current_bid
-                    .clone()
-                    .unwrap()
-                    .amount
-                    .into()
-            {
-                return Err(ContractError::BidNotEnough);
-            }
-
-            //We create the return funds message of previous bidder.
-            let return_funds_msg = BankMsg::Send {
-                to_address: auctions[auction_position.unwrap()]
-                    .current_bidder
-                    .clone()
-                    .unwrap()
-                    .into_string(),
-                amount: vec![auctions[auction_position.unwrap()]
-                    .current_bid
-                    .clone()
-                    .unwrap()],
-            };
+        let escrow = funds[0].clone();
+        let quantity_amount = Uint128::from(quantity);
 
-            resp = Response::new()
-                .add_message(return_funds_msg)
-                .add_attribute("method", "bid_with_refund")
-                .add_attribute("new_bidder", sender.clone())
-                .add_attribute(
-                    "old_bidder",
-                    auctions[auction_position.unwrap()]
-                        .current_bidder
-                        .clone()
-                        .unwrap(),
-                );
-        } else {
-            resp = Response::new()
-                .add_attribute("method", "bid")
-                .add_attribute("bidder", sender.clone());
+        if quantity == 0 || !(escrow.amount % quantity_amount).is_zero() {
+            return Err(ContractError::InvalidQuantity);
         }
 
-        //We update the new current highest offer in the contract state.
+        let offer_id = COLLECTION_OFFER_COUNT
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            + 1;
+        COLLECTION_OFFER_COUNT.save(deps.storage, &offer_id)?;
+
+        let offer = CollectionOffer {
+            id: offer_id,
+            nft_contract: nft_contract.into_string(),
+            buyer: sender.clone(),
+            price: coin((escrow.amount / quantity_amount).u128(), escrow.denom),
+            quantity,
+            expires,
+        };
 
-        auctions[auction_position.unwrap()].current_bidder = Some(sender);
-        auctions[auction_position.unwrap()].current_bid = Some(coin(new_bid_amount, auction_denom));
+        collection_offers().save(deps.storage, offer_id, &offer)?;
 
-        AUCTIONS.save(deps.storage, &auctions)?;
+        let placed = AuctionEvent::CollectionOfferPlaced {
+            offer_id,
+            collection: offer.nft_contract,
+            buyer: sender.clone(),
+            price: offer.price,
+            quantity,
+            expires,
+        };
 
-        Ok(resp)
+        let res = Response::new()
+            .add_attribute("method", "place_collection_offer")
+            .add_attribute("offer_id", offer_id.to_string())
+            .add_attribute("buyer", sender)
+            .add_attribute("quantity", quantity.to_string())
+            .add_event(placed.into());
+
+        Ok(res)
     }
 
-    pub fn buyout(
+    //Cancelling refunds the escrow of the tokens that were not bought yet. Same rules as single token offers apply.
+    pub fn cancel_collection_offer(
         deps: DepsMut<ArchwayQuery>,
+        env: Env,
         sender: Addr,
-        funds: Vec<Coin>,
-        nft_id: String,
-        blocktime: u64,
+        offer_id: u64,
     ) -> ArchwayResult<ContractError> {
-        let mut auctions = AUCTIONS.load(deps.storage)?;
-
-        //We check if the auction we want to buyout exists (get the position in the auction array)
-        let auction_position = auctions
-            .iter()
-            .position(|auction| auction.nft_id == nft_id.clone());
-
-        if auction_position.is_none() {
-            return Err(ContractError::NoAuction);
-        }
+        let offer = collection_offers()
+            .may_load(deps.storage, offer_id)?
+            .ok_or(ContractError::NoOffer)?;
 
-        //We check if the auction is finished or not.
-        if blocktime > auctions[auction_position.unwrap()].end_auction.seconds() {
-            return Err(ContractError::AuctionFinished)
+        if sender != offer.buyer && !offer.expires.is_expired(&env.block) {
+            return Err(ContractError::CannotCancelOffer);
         }
 
-        let auction_denom = auctions[auction_position.unwrap()].min_bid.clone().denom;
-        //We check if the buyer sent the funds wanted by the auction creator (and that they correspond to the right denom)
-        if funds
-            .iter()
-            .find(|coin| coin.denom == auctions[auction_position.unwrap()].min_bid.denom)
-            == None
-        {
-            return Err(ContractError::NoFunds);
-        }
+        collection_offers().remove(deps.storage, offer_id)?;
 
-        let buyout_amount = funds
-            .iter()
-            .find(|coin| coin.denom == auction_denom)
-            .unwrap()
+        let remaining = offer
+            .price
             .amount
-            .u128();
+            .checked_mul(Uint128::from(offer.quantity))
+            .map_err(StdError::from)?;
 
-        if buyout_amount
-            < auctions[auction_position.unwrap()]
-                .buyout_price
-                .amount
-                .into()
-        {
-            return Err(ContractError::PriceNotMet);
+        let refund = coin(remaining.u128(), offer.price.denom);
+
+        let return_funds_msg = BankMsg::Send {
+            to_address: offer.buyer.to_string(),
+            amount: vec![refund.clone()],
+        };
+
+        let cancelled = AuctionEvent::CollectionOfferCancelled {
+            offer_id,
+            collection: offer.nft_contract,
+            buyer: offer.buyer,
+            refund,
+        };
+
+        let res = Response::new()
+            .add_message(return_funds_msg)
+            .add_attribute("method", "cancel_collection_offer")
+            .add_attribute("offer_id", offer_id.to_string())
+            .add_event(cancelled.into());
+
+        Ok(res)
+    }
+
+    //Any holder of a token from the collection can fill one unit of the offer by sending the NFT to the house.
+    pub fn accept_collection_offer(
+        deps: DepsMut<ArchwayQuery>,
+        env: Env,
+        nft_contract: Addr,
+        seller: String,
+        nft_id: String,
+        offer_id: u64,
+    ) -> ArchwayResult<ContractError> {
+        check_not_paused(deps.storage, Operation::Settlement)?;
+
+        let mut offer = collection_offers()
+            .may_load(deps.storage, offer_id)?
+            .ok_or(ContractError::NoOffer)?;
+
+        if offer.nft_contract != nft_contract.as_str() {
+            return Err(ContractError::WrongNFT);
         }
 
-        //We check if there is already a bidder. If that's the case, we send his funds back because he lost the auction.
-        let mut resp;
+        if offer.expires.is_expired(&env.block) {
+            return Err(ContractError::OfferExpired);
+        }
 
-        if auctions[auction_position.unwrap()].current_bidder.is_some() {
-            //We create the return funds message of previous bidder.
-            let return_funds_msg = BankMsg::Send {
-                to_address: auctions[auction_position.unwrap()]
-                    .current_bidder
-                    .clone()
-                    .unwrap()
-                    .into_string(),
-                amount: vec![auctions[auction_position.unwrap()]
-                    .current_bid
-                    .clone()
-                    .unwrap()],
-            };
+        //The offer stays open until every token wanted has been bought
+        offer.quantity -= 1;
 
-            resp = Response::new()
-                .add_message(return_funds_msg)
-                .add_attribute("method", "buyout_with_refund")
-                .add_attribute("buyer", sender.clone())
-                .add_attribute(
-                    "old_bidder",
-                    auctions[auction_position.unwrap()]
-                        .current_bidder
-                        .clone()
-                        .unwrap(),
-                );
+        if offer.quantity == 0 {
+            collection_offers().remove(deps.storage, offer_id)?;
         } else {
-            resp = Response::new()
-                .add_attribute("method", "buyout")
-                .add_attribute("buyer", sender.clone());
+            collection_offers().save(deps.storage, offer_id, &offer)?;
         }
 
-        // We prepare the message to send the NFT to the buyer
-
         let send_nft_msg = Cw721ExecuteMsg::TransferNft {
-            recipient: sender.into_string(),
+            recipient: offer.buyer.to_string(),
             token_id: nft_id.clone(),
         };
 
         let wasm_send_nft = WasmMsg::Execute {
-            contract_addr: auctions[auction_position.unwrap()].nft_contract.clone(),
+            contract_addr: offer.nft_contract.clone(),
             msg: to_binary(&send_nft_msg)?,
             funds: vec![],
         };
 
-        resp = resp.add_message(wasm_send_nft);
+        let config = CONFIG.load(deps.storage)?;
+
+        let send_funds_msgs = payout_msgs(&config, &seller, offer.price.clone());
+
+        let filled = AuctionEvent::CollectionOfferFilled {
+            offer_id,
+            collection: offer.nft_contract,
+            nft_id: nft_id.clone(),
+            seller: deps.api.addr_validate(&seller)?,
+            buyer: offer.buyer.clone(),
+            price: offer.price,
+            remaining_quantity: offer.quantity,
+        };
+
+        let res = Response::new()
+            .add_message(wasm_send_nft)
+            .add_messages(send_funds_msgs)
+            .add_attribute("method", "accept_collection_offer")
+            .add_attribute("offer_id", offer_id.to_string())
+            .add_attribute("nft_id", nft_id)
+            .add_attribute("seller", seller)
+            .add_attribute("nft_receiver", offer.buyer)
+            .add_attribute("remaining_quantity", offer.quantity.to_string())
+            .add_event(filled.into());
+
+        Ok(res)
+    }
+}
+
+pub mod reply {
+    use archway_bindings::{types::rewards::WithdrawRewardsResponse, ArchwayQuery, ArchwayResult};
+    use cosmwasm_std::{
+        coin, from_binary, BankMsg, Coin, DepsMut, Env, Reply, Response, StdError, SubMsgResult,
+        Uint128,
+    };
+
+    use crate::{
+        error::ContractError,
+        events::AuctionEvent,
+        state::{
+            Epoch, EpochRewards, SplitRecipient, Withdrawal, CONFIG, CURRENT_EPOCH, EPOCH_REWARDS,
+            FAILED_SETTLEMENTS, PENDING_WITHDRAWAL, REWARDS_SPLIT, SETTLE_QUEUE, WITHDRAWALS,
+            WITHDRAWAL_COUNT,
+        },
+    };
+
+    use super::MAX_FEE_BPS;
+
+    pub const SETTLE_REPLY_ID: u64 = 1;
+    pub const WITHDRAW_REPLY_ID: u64 = 2;
+    pub const HOOK_REPLY_ID: u64 = 3;
 
-        //We remove the auction from the auction from the auctions array
+    //Settlements dispatched by `settle_expired` reply in the order they were sent, so the auction is the first one still queued.
+    //A failure is recorded so that the next calls skip the auction.
+    pub fn settled(deps: DepsMut<ArchwayQuery>, msg: Reply) -> ArchwayResult<ContractError> {
+        let mut queue = SETTLE_QUEUE.load(deps.storage)?;
 
-        auctions.retain(|auction| auction.nft_id != nft_id);
+        if queue.is_empty() {
+            return Err(StdError::not_found("settlement").into());
+        }
+
+        let nft_id = queue.remove(0);
+
+        SETTLE_QUEUE.save(deps.storage, &queue)?;
+
+        let resp = match msg.result {
+            SubMsgResult::Ok(_) => Response::new().add_attribute("settled", nft_id),
+            SubMsgResult::Err(err) => {
+                FAILED_SETTLEMENTS.save(deps.storage, &nft_id, &err)?;
 
-        AUCTIONS.save(deps.storage, &auctions)?;
+                Response::new()
+                    .add_attribute("settle_failed", nft_id)
+                    .add_attribute("reason", err)
+            }
+        };
 
         Ok(resp)
     }
 
-    pub fn close(
+    //Hooks only reply on error. The failing hook message is reverted and the auction goes on.
+    pub fn hook_failed(msg: Reply) -> ArchwayResult<ContractError> {
+        let resp = match msg.result {
+            SubMsgResult::Err(err) => Response::new().add_attribute("hook_failed", err),
+            SubMsgResult::Ok(_) => Response::new(),
+        };
+
+        Ok(resp)
+    }
+
+    //Closes the current epoch: the participants share of the withdrawn rewards is kept for the addresses that earned points
+    //during the epoch, the rest is split between the rewards recipients or sent to the fee recipient. Rewards withdrawn to another rewards address are not ours to share.
+    pub fn rewards_withdrawn(
         deps: DepsMut<ArchwayQuery>,
-        sender: Addr,
-        blocktime: u64,
-        nft_id: String,
+        env: Env,
+        msg: Reply,
     ) -> ArchwayResult<ContractError> {
-        let mut auctions = AUCTIONS.load(deps.storage)?;
+        let data = msg
+            .result
+            .into_result()
+            .map_err(StdError::generic_err)?
+            .data
+            .ok_or_else(|| StdError::generic_err("Missing withdrawal data"))?;
+
+        let withdrawal: WithdrawRewardsResponse = from_binary(&data)?;
+
+        let withdrawal_id = WITHDRAWAL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+        WITHDRAWAL_COUNT.save(deps.storage, &withdrawal_id)?;
+
+        let mode = PENDING_WITHDRAWAL.may_load(deps.storage)?;
+        PENDING_WITHDRAWAL.remove(deps.storage);
+
+        let log = Withdrawal {
+            id: withdrawal_id,
+            time: env.block.time,
+            rewards: withdrawal.total_rewards.clone(),
+            records: withdrawal.records_num,
+            mode,
+        };
 
-        //We check if the auction we want to close exists (get the position in the auction array)
-        let auction_position = auctions
-            .iter()
-            .position(|auction| auction.nft_id == nft_id.clone());
+        WITHDRAWALS.save(deps.storage, withdrawal_id, &log)?;
 
-        if auction_position.is_none() {
-            return Err(ContractError::NoAuction);
-        }
+        let withdrawn = AuctionEvent::RewardsWithdrawn {
+            withdrawal_id,
+            records: withdrawal.records_num,
+            rewards: withdrawal.total_rewards.clone(),
+        };
 
-        //If there are no bids, only the owner can close the auction
-        if auctions[auction_position.unwrap()].current_bid.is_none()
-            && sender != auctions[auction_position.unwrap()].owner
-        {
-            return Err(ContractError::Unauthorized);
+        let mut resp = Response::new()
+            .add_attribute("method", "rewards_withdrawn")
+            .add_attribute("withdrawal_id", withdrawal_id.to_string())
+            .add_attribute("records", withdrawal.records_num.to_string())
+            .add_event(withdrawn.into());
+
+        let config = CONFIG.load(deps.storage)?;
+
+        if config.rewards_address != env.contract.address {
+            return Ok(resp);
         }
 
-        let mut resp = Response::new();
+        let epoch = CURRENT_EPOCH.may_load(deps.storage)?.unwrap_or_default();
 
-        //If there are no bids and the owner closes, send the NFT back to him.
-        if auctions[auction_position.unwrap()].current_bid.is_none() {
-            let send_nft_msg = Cw721ExecuteMsg::TransferNft {
-                recipient: sender.into_string(),
-                token_id: nft_id.clone(),
-            };
+        let mut shared = vec![];
+        let mut house = vec![];
 
-            let wasm_send_nft = WasmMsg::Execute {
-                contract_addr: auctions[auction_position.unwrap()].nft_contract.clone(),
-                msg: to_binary(&send_nft_msg)?,
-                funds: vec![],
+        for reward in withdrawal.total_rewards {
+            //Nobody to share with if there was no activity
+            let share = if epoch.total_points == 0 {
+                Uint128::zero()
+            } else {
+                reward
+                    .amount
+                    .multiply_ratio(config.participants_share_bps, MAX_FEE_BPS)
             };
 
-            resp = resp
-                .add_message(wasm_send_nft)
-                .add_attribute("method", "close without bids");
+            let rest = reward.amount - share;
 
-        } else {
-            //If the auction is not finished but there are bids, only the owner can close the current auction for the current bid.
-            if blocktime < auctions[auction_position.unwrap()].end_auction.seconds()
-                && sender != auctions[auction_position.unwrap()].owner
-            {
-                return Err(ContractError::Unauthorized);
+            if !share.is_zero() {
+                shared.push(coin(share.u128(), reward.denom.clone()));
             }
 
-            //If the auction is not finished and there are bids, the owner can close it and accept the current bid without waiting for it to end.
-            if blocktime < auctions[auction_position.unwrap()].end_auction.seconds()
-                && sender == auctions[auction_position.unwrap()].owner
-            {
-                let send_nft_msg = Cw721ExecuteMsg::TransferNft {
-                    recipient: auctions[auction_position.unwrap()]
-                        .clone()
-                        .current_bidder
-                        .unwrap()
-                        .to_string(),
-                    token_id: nft_id.clone(),
-                };
+            if !rest.is_zero() {
+                house.push(coin(rest.u128(), reward.denom));
+            }
+        }
 
-                let wasm_send_nft = WasmMsg::Execute {
-                    contract_addr: auctions[auction_position.unwrap()].nft_contract.clone(),
-                    msg: to_binary(&send_nft_msg)?,
-                    funds: vec![],
-                };
+        let epoch_rewards = EpochRewards {
+            total_points: epoch.total_points,
+            rewards: shared,
+        };
 
-                let send_funds_msg = BankMsg::Send {
-                    to_address: sender.clone().into_string(),
-                    amount: vec![auctions[auction_position.unwrap()]
-                        .current_bid
-                        .clone()
-                        .unwrap()],
-                };
+        EPOCH_REWARDS.save(deps.storage, epoch.id, &epoch_rewards)?;
 
-                resp = resp
-                    .add_message(wasm_send_nft)
-                    .add_message(send_funds_msg)
-                    .add_attribute("method", "accept current bid")
-                    .add_attribute(
-                        "nft_receiver",
-                        auctions[auction_position.unwrap()]
-                            .clone()
-                            .current_bidder
-                            .unwrap(),
-                    );
+        let next_epoch = Epoch {
+            id: epoch.id + 1,
+            total_points: 0,
+        };
+
+        CURRENT_EPOCH.save(deps.storage, &next_epoch)?;
 
+        let split = REWARDS_SPLIT.may_load(deps.storage)?.unwrap_or_default();
+
+        if split.is_empty() {
+            if !house.is_empty() {
+                resp = resp.add_message(BankMsg::Send {
+                    to_address: config.fee_recipient.to_string(),
+                    amount: house,
+                });
             }
+        } else {
+            resp = resp.add_messages(split_msgs(&split, house));
+        }
 
-            //If auction is finished, both auction creator or winner can close it.
-            if blocktime > auctions[auction_position.unwrap()].end_auction.seconds() {
+        Ok(resp.add_attribute("closed_epoch", epoch.id.to_string()))
+    }
 
-                if sender.clone() != auctions[auction_position.unwrap()].owner || sender != auctions[auction_position.unwrap()].clone().current_bidder.unwrap() {
-                    return Err(ContractError::CannotClose)
-                }
+    //Every recipient gets its weight of each coin, the rounding leftovers go to the last one so nothing stays in the house.
+    fn split_msgs(split: &[SplitRecipient], rewards: Vec<Coin>) -> Vec<BankMsg> {
+        let mut amounts: Vec<Vec<Coin>> = vec![vec![]; split.len()];
 
-                let send_nft_msg = Cw721ExecuteMsg::TransferNft {
-                    recipient: auctions[auction_position.unwrap()]
-                        .clone()
-                        .current_bidder
-                        .unwrap()
-                        .to_string(),
-                    token_id: nft_id.clone(),
-                };
+        for reward in rewards {
+            let mut left = reward.amount;
 
-                let wasm_send_nft = WasmMsg::Execute {
-                    contract_addr: auctions[auction_position.unwrap()].nft_contract.clone(),
-                    msg: to_binary(&send_nft_msg)?,
-                    funds: vec![],
+            for (position, recipient) in split.iter().enumerate() {
+                let amount = if position == split.len() - 1 {
+                    left
+                } else {
+                    reward
+                        .amount
+                        .multiply_ratio(recipient.weight_bps, MAX_FEE_BPS)
                 };
 
-                let send_funds_msg = BankMsg::Send {
-                    to_address: sender.into_string(),
-                    amount: vec![auctions[auction_position.unwrap()]
-                        .current_bid
-                        .clone()
-                        .unwrap()],
-                };
+                left -= amount;
 
-                resp = resp
-                    .add_message(wasm_send_nft)
-                    .add_message(send_funds_msg)
-                    .add_attribute("method", "close a completed auction")
-                    .add_attribute(
-                        "nft_receiver",
-                        auctions[auction_position.unwrap()]
-                            .clone()
-                            .current_bidder
-                            .unwrap(),
-                    );
+                if !amount.is_zero() {
+                    amounts[position].push(coin(amount.u128(), reward.denom.clone()));
+                }
             }
         }
 
-        //Remove the auction that was closed.
-
-        auctions.retain(|auction| auction.nft_id != nft_id);
-
-        AUCTIONS.save(deps.storage, &auctions)?;
-
-        Ok(resp)
+        split
+            .iter()
+            .zip(amounts)
+            .filter(|(_, amount)| !amount.is_empty())
+            .map(|(recipient, amount)| BankMsg::Send {
+                to_address: recipient.address.to_string(),
+                amount,
+            })
+            .collect()
     }
 }
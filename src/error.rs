@@ -1,6 +1,8 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
+use crate::state::{Operation, Role};
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
@@ -9,9 +11,93 @@ pub enum ContractError {
     #[error("Not owner, unauthorized")]
     Unauthorized,
 
+    #[error("Missing {0} role, unauthorized")]
+    MissingRole(Role),
+
+    #[error("{0} is paused")]
+    Paused(Operation),
+
     #[error("Must have atleast 1 owner")]
     NoOwner,
 
+    #[error("Threshold must be between 1 and the number of owners")]
+    InvalidThreshold,
+
+    #[error("Proposal does not exist")]
+    NoProposal,
+
+    #[error("Proposal has expired")]
+    ProposalExpired,
+
+    #[error("Proposal already approved by this owner")]
+    AlreadyApproved,
+
+    #[error("Fee can't be more than 10000 basis points")]
+    InvalidFee,
+
+    #[error("Participants share can't be more than 10000 basis points")]
+    InvalidShare,
+
+    #[error("No rewards to claim")]
+    NothingToClaim,
+
+    #[error("Split weights must be positive and add up to 10000 basis points")]
+    InvalidSplit,
+
+    #[error("No rewards split configured")]
+    NoRewardsSplit,
+
+    #[error("Rewards are not sent to the auction house")]
+    RewardsNotHeld,
+
+    #[error("Withdraw either by limit or by record ids, not both")]
+    ConflictingWithdrawal,
+
+    #[error("Hook is already registered")]
+    HookAlreadyRegistered,
+
+    #[error("Hook is not registered")]
+    HookNotRegistered,
+
+    #[error("No escrowed bid to retract")]
+    NoBidToRetract,
+
+    #[error("The current bid can only be retracted if the auction was edited after it")]
+    RetractionNotAllowed,
+
+    #[error("This auction can't be bought out")]
+    NoBuyout,
+
+    #[error("Invalid auction update")]
+    InvalidAuctionUpdate,
+
+    #[error("Once there are bids, the minimum bid and buyout price can only be lowered")]
+    EditNotAllowed,
+
+    #[error("Auction has not started yet")]
+    NotStarted,
+
+    #[error("Start time must be in the future, at most the maximum auction duration ahead")]
+    InvalidStartTime,
+
+    #[error("Minimum bid increment can't be more than 10000 basis points")]
+    InvalidIncrement,
+
+    #[error("Invalid bidder gate")]
+    InvalidGate,
+
+    #[error("Sender doesn't pass the bidder gate of this auction")]
+    NotAllowedToBid,
+
+    #[error("Auction duration is outside of the allowed bounds")]
+    InvalidDuration,
+
+    #[error("At least one denom must be accepted")]
+    NoDenoms,
+
+    #[error("Denom {0} is not accepted by the auction house")]
+    DenomNotAccepted(String),
+
     #[error("Auction does not exist")]
     NoAuction,
 
@@ -21,6 +107,18 @@ pub enum ContractError {
     #[error("NFT does not exist")]
     NoNFT,
 
+    #[error("Token was not deposited by the sender")]
+    NotDepositor,
+
+    #[error("A lot needs at least one token")]
+    EmptyLot,
+
+    #[error("Token is listed twice in the same lot")]
+    DuplicateToken,
+
+    #[error("A batch needs at least one auction")]
+    EmptyBatch,
+
     #[error("No bid funds sent")]
     NoFunds,
 
@@ -30,12 +128,51 @@ pub enum ContractError {
     #[error("Bid is lower than current bid")]
     BidNotEnough,
 
+    #[error("Sellers can't bid on or buy out their own auction")]
+    OwnAuction,
+
     #[error("Buyout price not met")]
     PriceNotMet,
 
     #[error("Only winner or owner can close the auction")]
     CannotClose,
 
+    #[error("Only auctions that failed to settle can be dropped")]
+    SettlementNotFailed,
+
     #[error("Auction already finished, can't bid or buyout anymore")]
     AuctionFinished,
+
+    #[error("Unknown reply id {0}")]
+    UnknownReply(u64),
+
+    #[error("Cannot migrate from contract {0}")]
+    WrongContract(String),
+
+    #[error("Invalid contract version: {0}")]
+    InvalidVersion(String),
+
+    #[error("Cannot migrate from version {0} to an older version")]
+    CannotDowngrade(String),
+
+    #[error("Migrating from a version without config requires `legacy_config`")]
+    MissingLegacyConfig,
+
+    #[error("Offer does not exist")]
+    NoOffer,
+
+    #[error("Offer has expired")]
+    OfferExpired,
+
+    #[error("Expiration is already in the past")]
+    InvalidExpiration,
+
+    #[error("Only the buyer can cancel an offer before it expires")]
+    CannotCancelOffer,
+
+    #[error("Received NFT does not match the offer")]
+    WrongNFT,
+
+    #[error("Quantity must be positive and split the sent funds evenly")]
+    InvalidQuantity,
 }
@@ -0,0 +1,681 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{coin, Addr, Coin, Event, Timestamp};
+use cw_utils::Expiration;
+use schemars::schema::{
+    InstanceType, Metadata, ObjectValidation, RootSchema, Schema, SchemaObject, SubschemaValidation,
+};
+
+use crate::state::Auction;
+
+//Events emitted on every state transition, indexers can rely on their type and attributes. An auction is identified
+//by the `nft_id` of its first token. Amounts are formatted like `100aconst` and timestamps are in seconds. The
+//attributes emitted for each event are described by `event_schema`.
+#[cw_serde]
+pub enum AuctionEvent {
+    Listed {
+        auction_id: String,
+        collection: String,
+        seller: Addr,
+        min_bid: Coin,
+        buyout_price: Option<Coin>,
+        start_time: Option<Timestamp>,
+        end_time: Timestamp,
+        lot_size: u64,
+    },
+    //Listing after a seller edit
+    Edited {
+        auction_id: String,
+        collection: String,
+        seller: Addr,
+        min_bid: Coin,
+        buyout_price: Option<Coin>,
+        end_time: Timestamp,
+    },
+    BidPlaced {
+        auction_id: String,
+        collection: String,
+        bidder: Addr,
+        amount: Coin,
+        end_time: Timestamp,
+    },
+    //The previous highest bid stays escrowed until its bidder takes it back or the auction ends
+    Outbid {
+        auction_id: String,
+        collection: String,
+        bidder: Addr,
+        amount: Coin,
+        outbid_at: Timestamp,
+    },
+    //Every escrowed bid of the bidder is refunded. When the current bid is retracted the latest outbid bid leads again,
+    //the auction is left without bid if there is none.
+    BidRetracted {
+        auction_id: String,
+        collection: String,
+        bidder: Addr,
+        refund: Coin,
+        current_bidder: Option<Addr>,
+        current_bid: Option<Coin>,
+    },
+    BoughtOut {
+        auction_id: String,
+        collection: String,
+        seller: Addr,
+        buyer: Addr,
+        price: Coin,
+        bought_at: Timestamp,
+    },
+    //Without a winner the lot went back to the seller
+    Settled {
+        auction_id: String,
+        collection: String,
+        seller: Addr,
+        winner: Option<Addr>,
+        price: Option<Coin>,
+        settled_at: Timestamp,
+    },
+    //Delisted by a moderator, or by the owners when the lot goes to someone else than the seller
+    Cancelled {
+        auction_id: String,
+        collection: String,
+        seller: Addr,
+        moderator: Addr,
+        reason: String,
+        nft_receiver: Addr,
+    },
+    //Dropped by a moderator after its settlement failed, the lot stays in the house
+    Dropped {
+        auction_id: String,
+        collection: String,
+        seller: Addr,
+        moderator: Addr,
+        reason: String,
+    },
+    //Flagged or unflagged by a moderator, the reason is empty once unflagged
+    Flagged {
+        auction_id: String,
+        collection: String,
+        moderator: Addr,
+        reason: Option<String>,
+    },
+    OwnerAdded {
+        owner: Addr,
+    },
+    OwnerRemoved {
+        owner: Addr,
+    },
+    RewardsWithdrawn {
+        withdrawal_id: u64,
+        records: u64,
+        rewards: Vec<Coin>,
+    },
+    OfferPlaced {
+        offer_id: u64,
+        collection: String,
+        nft_id: String,
+        buyer: Addr,
+        amount: Coin,
+        expires: Expiration,
+    },
+    OfferAccepted {
+        offer_id: u64,
+        collection: String,
+        nft_id: String,
+        seller: Addr,
+        buyer: Addr,
+        amount: Coin,
+    },
+    //Cancelled by the buyer, or by anyone once expired. The escrowed amount always goes back to the buyer.
+    OfferCancelled {
+        offer_id: u64,
+        collection: String,
+        nft_id: String,
+        buyer: Addr,
+        refund: Coin,
+    },
+    //`price` is paid for each of the `quantity` tokens wanted
+    CollectionOfferPlaced {
+        offer_id: u64,
+        collection: String,
+        buyer: Addr,
+        price: Coin,
+        quantity: u32,
+        expires: Expiration,
+    },
+    //One token was sold, the offer is removed once `remaining_quantity` reaches 0
+    CollectionOfferFilled {
+        offer_id: u64,
+        collection: String,
+        nft_id: String,
+        seller: Addr,
+        buyer: Addr,
+        price: Coin,
+        remaining_quantity: u32,
+    },
+    CollectionOfferCancelled {
+        offer_id: u64,
+        collection: String,
+        buyer: Addr,
+        refund: Coin,
+    },
+}
+
+impl AuctionEvent {
+    pub fn listed(auction: &Auction) -> Self {
+        AuctionEvent::Listed {
+            auction_id: auction.nft_id.clone(),
+            collection: auction.nft_contract.clone(),
+            seller: auction.owner.clone(),
+            min_bid: auction.min_bid.clone(),
+            buyout_price: auction.buyout_price.clone(),
+            start_time: auction.start_time,
+            end_time: auction.end_auction,
+            lot_size: auction.tokens().len() as u64,
+        }
+    }
+
+    //One event of every kind, the values are only placeholders.
+    pub fn samples() -> Vec<AuctionEvent> {
+        let address = Addr::unchecked("address");
+        let amount = coin(100, "aconst");
+        let time = Timestamp::from_seconds(0);
+        let text = String::new();
+
+        vec![
+            AuctionEvent::Listed {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                seller: address.clone(),
+                min_bid: amount.clone(),
+                buyout_price: None,
+                start_time: None,
+                end_time: time,
+                lot_size: 1,
+            },
+            AuctionEvent::Edited {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                seller: address.clone(),
+                min_bid: amount.clone(),
+                buyout_price: None,
+                end_time: time,
+            },
+            AuctionEvent::BidPlaced {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                bidder: address.clone(),
+                amount: amount.clone(),
+                end_time: time,
+            },
+            AuctionEvent::Outbid {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                bidder: address.clone(),
+                amount: amount.clone(),
+                outbid_at: time,
+            },
+            AuctionEvent::BidRetracted {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                bidder: address.clone(),
+                refund: amount.clone(),
+                current_bidder: None,
+                current_bid: None,
+            },
+            AuctionEvent::BoughtOut {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                seller: address.clone(),
+                buyer: address.clone(),
+                price: amount.clone(),
+                bought_at: time,
+            },
+            AuctionEvent::Settled {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                seller: address.clone(),
+                winner: None,
+                price: None,
+                settled_at: time,
+            },
+            AuctionEvent::Cancelled {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                seller: address.clone(),
+                moderator: address.clone(),
+                reason: text.clone(),
+                nft_receiver: address.clone(),
+            },
+            AuctionEvent::Dropped {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                seller: address.clone(),
+                moderator: address.clone(),
+                reason: text.clone(),
+            },
+            AuctionEvent::Flagged {
+                auction_id: text.clone(),
+                collection: text.clone(),
+                moderator: address.clone(),
+                reason: None,
+            },
+            AuctionEvent::OwnerAdded {
+                owner: address.clone(),
+            },
+            AuctionEvent::OwnerRemoved {
+                owner: address.clone(),
+            },
+            AuctionEvent::RewardsWithdrawn {
+                withdrawal_id: 1,
+                records: 1,
+                rewards: vec![amount.clone()],
+            },
+            AuctionEvent::OfferPlaced {
+                offer_id: 1,
+                collection: text.clone(),
+                nft_id: text.clone(),
+                buyer: address.clone(),
+                amount: amount.clone(),
+                expires: Expiration::Never {},
+            },
+            AuctionEvent::OfferAccepted {
+                offer_id: 1,
+                collection: text.clone(),
+                nft_id: text.clone(),
+                seller: address.clone(),
+                buyer: address.clone(),
+                amount: amount.clone(),
+            },
+            AuctionEvent::OfferCancelled {
+                offer_id: 1,
+                collection: text.clone(),
+                nft_id: text.clone(),
+                buyer: address.clone(),
+                refund: amount.clone(),
+            },
+            AuctionEvent::CollectionOfferPlaced {
+                offer_id: 1,
+                collection: text.clone(),
+                buyer: address.clone(),
+                price: amount.clone(),
+                quantity: 1,
+                expires: Expiration::Never {},
+            },
+            AuctionEvent::CollectionOfferFilled {
+                offer_id: 1,
+                collection: text.clone(),
+                nft_id: text.clone(),
+                seller: address.clone(),
+                buyer: address.clone(),
+                price: amount.clone(),
+                remaining_quantity: 0,
+            },
+            AuctionEvent::CollectionOfferCancelled {
+                offer_id: 1,
+                collection: text,
+                buyer: address,
+                refund: amount,
+            },
+        ]
+    }
+}
+
+//Format of the value of every attribute key, whatever the event
+fn attribute_format(key: &str) -> Option<&'static str> {
+    let format = match key {
+        "auction_id" => "`nft_id` of the first token of the auction",
+        "collection" => "Address of the cw721 contract",
+        "nft_id" => "Token id in the collection",
+        "seller" | "bidder" | "buyer" | "moderator" | "owner" | "nft_receiver" => "Address",
+        "winner" | "current_bidder" => "Address, empty if there is none",
+        "min_bid" | "amount" | "price" | "refund" => "Amount like `100aconst`",
+        "buyout_price" | "current_bid" => "Amount like `100aconst`, empty if there is none",
+        "rewards" => "Comma separated amounts like `100aconst,5uother`",
+        "end_time" | "outbid_at" | "bought_at" | "settled_at" => "Timestamp in seconds",
+        "start_time" => "Timestamp in seconds, empty if the auction starts right away",
+        "expires" => "`height:<height>`, `time:<seconds>` or `never`",
+        "lot_size" | "withdrawal_id" | "records" | "offer_id" | "quantity"
+        | "remaining_quantity" => "Unsigned integer",
+        "flagged" => "`true` or `false`",
+        "reason" => "Free text, empty when an auction is unflagged",
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+//Events as they appear in transaction results: their type is prefixed with `wasm-` and every attribute is a string.
+//Each event kind is an object titled with its type, its properties are the attribute keys.
+pub fn event_schema() -> RootSchema {
+    let events = AuctionEvent::samples()
+        .into_iter()
+        .map(|event| {
+            let event = Event::from(event);
+
+            let properties = event
+                .attributes
+                .iter()
+                .map(|attr| {
+                    //Adding an attribute key without its format is caught when exporting the schema
+                    let format = attribute_format(&attr.key)
+                        .unwrap_or_else(|| panic!("Undocumented attribute `{}`", attr.key));
+
+                    (attr.key.clone(), attribute_schema(format))
+                })
+                .collect();
+
+            let object = ObjectValidation {
+                required: event.attributes.iter().map(|attr| attr.key.clone()).collect(),
+                properties,
+                additional_properties: Some(Box::new(Schema::Bool(false))),
+                ..Default::default()
+            };
+
+            Schema::Object(SchemaObject {
+                metadata: Some(Box::new(Metadata {
+                    title: Some(format!("wasm-{}", event.ty)),
+                    ..Default::default()
+                })),
+                instance_type: Some(InstanceType::Object.into()),
+                object: Some(Box::new(object)),
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    RootSchema {
+        meta_schema: Some("http://json-schema.org/draft-07/schema#".to_string()),
+        schema: SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                title: Some("AuctionEvent".to_string()),
+                description: Some(
+                    "Events emitted by the auction house, titled with their type. Every attribute \
+                     value is a string."
+                        .to_string(),
+                ),
+                ..Default::default()
+            })),
+            subschemas: Some(Box::new(SubschemaValidation {
+                one_of: Some(events),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+        definitions: Default::default(),
+    }
+}
+
+fn attribute_schema(format: &str) -> Schema {
+    Schema::Object(SchemaObject {
+        metadata: Some(Box::new(Metadata {
+            description: Some(format.to_string()),
+            ..Default::default()
+        })),
+        instance_type: Some(InstanceType::String.into()),
+        ..Default::default()
+    })
+}
+
+//Missing amounts are empty attributes
+fn coin_to_string(coin: Option<Coin>) -> String {
+    coin.map(|coin| coin.to_string()).unwrap_or_default()
+}
+
+fn expiration_to_string(expires: Expiration) -> String {
+    match expires {
+        Expiration::AtHeight(height) => format!("height:{}", height),
+        Expiration::AtTime(time) => format!("time:{}", time.seconds()),
+        Expiration::Never {} => "never".to_string(),
+    }
+}
+
+fn coins_to_string(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl From<AuctionEvent> for Event {
+    fn from(event: AuctionEvent) -> Self {
+        match event {
+            AuctionEvent::Listed {
+                auction_id,
+                collection,
+                seller,
+                min_bid,
+                buyout_price,
+                start_time,
+                end_time,
+                lot_size,
+            } => Event::new("auction_listed")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("seller", seller)
+                .add_attribute("min_bid", min_bid.to_string())
+                .add_attribute("buyout_price", coin_to_string(buyout_price))
+                .add_attribute(
+                    "start_time",
+                    start_time
+                        .map(|start_time| start_time.seconds().to_string())
+                        .unwrap_or_default(),
+                )
+                .add_attribute("end_time", end_time.seconds().to_string())
+                .add_attribute("lot_size", lot_size.to_string()),
+            AuctionEvent::Edited {
+                auction_id,
+                collection,
+                seller,
+                min_bid,
+                buyout_price,
+                end_time,
+            } => Event::new("auction_edited")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("seller", seller)
+                .add_attribute("min_bid", min_bid.to_string())
+                .add_attribute("buyout_price", coin_to_string(buyout_price))
+                .add_attribute("end_time", end_time.seconds().to_string()),
+            AuctionEvent::BidPlaced {
+                auction_id,
+                collection,
+                bidder,
+                amount,
+                end_time,
+            } => Event::new("auction_bid_placed")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("bidder", bidder)
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("end_time", end_time.seconds().to_string()),
+            AuctionEvent::Outbid {
+                auction_id,
+                collection,
+                bidder,
+                amount,
+                outbid_at,
+            } => Event::new("auction_outbid")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("bidder", bidder)
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("outbid_at", outbid_at.seconds().to_string()),
+            AuctionEvent::BidRetracted {
+                auction_id,
+                collection,
+                bidder,
+                refund,
+                current_bidder,
+                current_bid,
+            } => Event::new("auction_bid_retracted")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("bidder", bidder)
+                .add_attribute("refund", refund.to_string())
+                .add_attribute(
+                    "current_bidder",
+                    current_bidder.map(String::from).unwrap_or_default(),
+                )
+                .add_attribute("current_bid", coin_to_string(current_bid)),
+            AuctionEvent::BoughtOut {
+                auction_id,
+                collection,
+                seller,
+                buyer,
+                price,
+                bought_at,
+            } => Event::new("auction_bought_out")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("seller", seller)
+                .add_attribute("buyer", buyer)
+                .add_attribute("price", price.to_string())
+                .add_attribute("bought_at", bought_at.seconds().to_string()),
+            AuctionEvent::Settled {
+                auction_id,
+                collection,
+                seller,
+                winner,
+                price,
+                settled_at,
+            } => Event::new("auction_settled")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("seller", seller)
+                .add_attribute("winner", winner.map(String::from).unwrap_or_default())
+                .add_attribute("price", coin_to_string(price))
+                .add_attribute("settled_at", settled_at.seconds().to_string()),
+            AuctionEvent::Cancelled {
+                auction_id,
+                collection,
+                seller,
+                moderator,
+                reason,
+                nft_receiver,
+            } => Event::new("auction_cancelled")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("seller", seller)
+                .add_attribute("moderator", moderator)
+                .add_attribute("reason", reason)
+                .add_attribute("nft_receiver", nft_receiver),
+            AuctionEvent::Dropped {
+                auction_id,
+                collection,
+                seller,
+                moderator,
+                reason,
+            } => Event::new("auction_dropped")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("seller", seller)
+                .add_attribute("moderator", moderator)
+                .add_attribute("reason", reason),
+            AuctionEvent::Flagged {
+                auction_id,
+                collection,
+                moderator,
+                reason,
+            } => Event::new("auction_flagged")
+                .add_attribute("auction_id", auction_id)
+                .add_attribute("collection", collection)
+                .add_attribute("moderator", moderator)
+                .add_attribute("flagged", reason.is_some().to_string())
+                .add_attribute("reason", reason.unwrap_or_default()),
+            AuctionEvent::OwnerAdded { owner } => {
+                Event::new("owner_added").add_attribute("owner", owner)
+            }
+            AuctionEvent::OwnerRemoved { owner } => {
+                Event::new("owner_removed").add_attribute("owner", owner)
+            }
+            AuctionEvent::RewardsWithdrawn {
+                withdrawal_id,
+                records,
+                rewards,
+            } => Event::new("rewards_withdrawn")
+                .add_attribute("withdrawal_id", withdrawal_id.to_string())
+                .add_attribute("records", records.to_string())
+                .add_attribute("rewards", coins_to_string(&rewards)),
+            AuctionEvent::OfferPlaced {
+                offer_id,
+                collection,
+                nft_id,
+                buyer,
+                amount,
+                expires,
+            } => Event::new("offer_placed")
+                .add_attribute("offer_id", offer_id.to_string())
+                .add_attribute("collection", collection)
+                .add_attribute("nft_id", nft_id)
+                .add_attribute("buyer", buyer)
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("expires", expiration_to_string(expires)),
+            AuctionEvent::OfferAccepted {
+                offer_id,
+                collection,
+                nft_id,
+                seller,
+                buyer,
+                amount,
+            } => Event::new("offer_accepted")
+                .add_attribute("offer_id", offer_id.to_string())
+                .add_attribute("collection", collection)
+                .add_attribute("nft_id", nft_id)
+                .add_attribute("seller", seller)
+                .add_attribute("buyer", buyer)
+                .add_attribute("amount", amount.to_string()),
+            AuctionEvent::OfferCancelled {
+                offer_id,
+                collection,
+                nft_id,
+                buyer,
+                refund,
+            } => Event::new("offer_cancelled")
+                .add_attribute("offer_id", offer_id.to_string())
+                .add_attribute("collection", collection)
+                .add_attribute("nft_id", nft_id)
+                .add_attribute("buyer", buyer)
+                .add_attribute("refund", refund.to_string()),
+            AuctionEvent::CollectionOfferPlaced {
+                offer_id,
+                collection,
+                buyer,
+                price,
+                quantity,
+                expires,
+            } => Event::new("collection_offer_placed")
+                .add_attribute("offer_id", offer_id.to_string())
+                .add_attribute("collection", collection)
+                .add_attribute("buyer", buyer)
+                .add_attribute("price", price.to_string())
+                .add_attribute("quantity", quantity.to_string())
+                .add_attribute("expires", expiration_to_string(expires)),
+            AuctionEvent::CollectionOfferFilled {
+                offer_id,
+                collection,
+                nft_id,
+                seller,
+                buyer,
+                price,
+                remaining_quantity,
+            } => Event::new("collection_offer_filled")
+                .add_attribute("offer_id", offer_id.to_string())
+                .add_attribute("collection", collection)
+                .add_attribute("nft_id", nft_id)
+                .add_attribute("seller", seller)
+                .add_attribute("buyer", buyer)
+                .add_attribute("price", price.to_string())
+                .add_attribute("remaining_quantity", remaining_quantity.to_string()),
+            AuctionEvent::CollectionOfferCancelled {
+                offer_id,
+                collection,
+                buyer,
+                refund,
+            } => Event::new("collection_offer_cancelled")
+                .add_attribute("offer_id", offer_id.to_string())
+                .add_attribute("collection", collection)
+                .add_attribute("buyer", buyer)
+                .add_attribute("refund", refund.to_string()),
+        }
+    }
+}
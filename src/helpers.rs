@@ -0,0 +1,90 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Addr, Coin, CosmosMsg, CustomQuery, QuerierWrapper, StdResult, WasmMsg,
+};
+
+use crate::{
+    msg::{AuctionParams, ExecMsg, OpenAuctionsResp, QueryMsg},
+    state::Auction,
+};
+
+//Typed wrapper around an auction house address, for contracts and backends that talk to the house. Messages are generic
+//over the custom message type so they can be added to a `Response<ArchwayMsg>` as well as a plain `Response`.
+#[cw_serde]
+pub struct AuctionHouseContract(pub Addr);
+
+impl AuctionHouseContract {
+    pub fn addr(&self) -> Addr {
+        self.0.clone()
+    }
+
+    pub fn call<C>(&self, msg: ExecMsg, funds: Vec<Coin>) -> StdResult<CosmosMsg<C>> {
+        let msg = WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&msg)?,
+            funds,
+        };
+
+        Ok(msg.into())
+    }
+
+    //The token must have been deposited with `ReceiveMsg::Deposit`, use `ReceiveMsg::CreateAuction` with `SendNft` to list
+    //it in one step.
+    pub fn create_auction_msg<C>(
+        &self,
+        nft_id: impl Into<String>,
+        nft_contract: impl Into<String>,
+        params: AuctionParams,
+    ) -> StdResult<CosmosMsg<C>> {
+        let msg = ExecMsg::CreateAuction {
+            nft_id: nft_id.into(),
+            nft_contract: nft_contract.into(),
+            min_bid: params.min_bid,
+            buyout: params.buyout,
+            denom: params.denom,
+            duration: params.duration,
+            start_time: params.start_time,
+            gate: params.gate,
+        };
+
+        self.call(msg, vec![])
+    }
+
+    pub fn bid_msg<C>(
+        &self,
+        nft_id: impl Into<String>,
+        funds: Vec<Coin>,
+    ) -> StdResult<CosmosMsg<C>> {
+        let msg = ExecMsg::Bid {
+            nft_id: nft_id.into(),
+            proof: None,
+        };
+
+        self.call(msg, funds)
+    }
+
+    pub fn query_auction<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+        nft_id: impl Into<String>,
+    ) -> StdResult<Auction> {
+        let msg = QueryMsg::Auction {
+            nft_id: nft_id.into(),
+        };
+
+        querier.query_wasm_smart(self.addr(), &msg)
+    }
+
+    pub fn query_open_auctions<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<Auction>> {
+        let msg = QueryMsg::OpenAuctions { start_after, limit };
+
+        let resp: OpenAuctionsResp = querier.query_wasm_smart(self.addr(), &msg)?;
+
+        Ok(resp.auctions)
+    }
+}
@@ -0,0 +1,53 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Addr, Binary, Coin, CosmosMsg, StdResult, WasmMsg};
+
+//Notifications sent to the hook contracts registered by the owners. Hooks run as sub messages whose failure is ignored,
+//so they can't block an auction.
+#[cw_serde]
+pub enum AuctionHookMsg {
+    Listed {
+        auction_id: String,
+        collection: String,
+        seller: Addr,
+    },
+    Bid {
+        auction_id: String,
+        bidder: Addr,
+        amount: Coin,
+    },
+    //Sold to the winner of the auction or to a buyer, or returned to the seller when there is no winner
+    Settled {
+        auction_id: String,
+        seller: Addr,
+        winner: Option<Addr>,
+        price: Option<Coin>,
+    },
+    Cancelled {
+        auction_id: String,
+        reason: String,
+    },
+}
+
+impl AuctionHookMsg {
+    //Serializes the message as `{"auction_hook": {...}}`, which hook contracts must handle.
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = AuctionHookExecuteMsg::AuctionHook(self);
+        to_binary(&msg)
+    }
+
+    pub fn into_cosmos_msg<C>(self, contract_addr: impl Into<String>) -> StdResult<CosmosMsg<C>> {
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: self.into_binary()?,
+            funds: vec![],
+        };
+
+        Ok(execute.into())
+    }
+}
+
+//Wrapper so the hook message is a variant of the hook contract `ExecuteMsg`
+#[cw_serde]
+enum AuctionHookExecuteMsg {
+    AuctionHook(AuctionHookMsg),
+}
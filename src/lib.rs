@@ -1,26 +1,44 @@
 use archway_bindings::{ArchwayQuery, ArchwayResult};
 use contract::exec::{
-    add_owner, bid, buyout, create_auction, remove_owner, update_rewards_address, withdraw_rewards, close,
+    approve, batch_create_auction, bid, buyout, cancel_collection_offer, cancel_offer,
+    claim_rewards, close, create_auction, create_lot_auction, distribute_rewards, drop_auction,
+    flag_auction, force_cancel, grant_role, place_collection_offer, place_offer, propose,
+    receive_nft, retract_bid, revoke_role, set_paused, settle_expired, update_auction, update_fees,
+    withdraw_deposit, withdraw_rewards,
 };
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, StdResult};
 use error::ContractError;
-use msg::{ExecMsg, InstantiateMsg};
+use msg::{AuctionParams, AuctionUpdate, ExecMsg, InstantiateMsg, MigrateMsg};
 
 mod contract;
 pub mod error;
+pub mod events;
+pub mod helpers;
+pub mod hooks;
 pub mod msg;
-mod state;
+#[cfg(any(test, feature = "tests"))]
+pub mod multitest;
+pub mod state;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
+    deps: DepsMut<ArchwayQuery>,
+    env: Env,
     info: MessageInfo,
-    _msg: InstantiateMsg,
-) -> StdResult<Response> {
-    contract::instantiate(deps, info.sender)
+    msg: InstantiateMsg,
+) -> ArchwayResult<ContractError> {
+    contract::instantiate(deps, env, info.sender, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<ArchwayQuery>,
+    env: Env,
+    msg: MigrateMsg,
+) -> ArchwayResult<ContractError> {
+    contract::migrate(deps, env, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -29,9 +47,100 @@ pub fn query(deps: Deps<ArchwayQuery>, env: Env, msg: msg::QueryMsg) -> StdResul
     use msg::QueryMsg::*;
 
     match msg {
-        OpenAuctions {} => to_binary(&query::open_auctions(deps)?),
+        Config {} => to_binary(&query::config(deps)?),
+        Proposal { proposal_id } => to_binary(&query::proposal(deps, proposal_id)?),
+        RolesOf { address } => to_binary(&query::roles_of(deps, address)?),
+        Roles { start_after, limit } => to_binary(&query::roles(deps, start_after, limit)?),
+        PendingProposals { start_after, limit } => to_binary(&query::pending_proposals(
+            deps,
+            env,
+            start_after,
+            limit,
+        )?),
+        Paused {} => to_binary(&query::paused(deps)?),
+        Auction { nft_id } => to_binary(&query::auction(deps, nft_id)?),
+        OpenAuctions { start_after, limit } => {
+            to_binary(&query::open_auctions(deps, start_after, limit)?)
+        }
+        UpcomingAuctions { start_after, limit } => {
+            to_binary(&query::upcoming_auctions(deps, env, start_after, limit)?)
+        }
         Metadata {} => to_binary(&query::contract_metadata(deps, env)?),
-        OutstandingRewards {} => to_binary(&query::outstanding_rewards(deps, env)?),
+        FlatFee {} => to_binary(&query::flat_fee(deps, env)?),
+        ArchivedAuction { id } => to_binary(&query::archived_auction(deps, id)?),
+        ArchivedAuctions {
+            start_before,
+            limit,
+        } => to_binary(&query::archived_auctions(deps, start_before, limit)?),
+        FailedSettlements { start_after, limit } => {
+            to_binary(&query::failed_settlements(deps, start_after, limit)?)
+        }
+        OutstandingRewards {} => to_binary(&query::outstanding_rewards(deps)?),
+        RewardsSplit {} => to_binary(&query::rewards_split(deps)?),
+        Hooks {} => to_binary(&query::hooks(deps)?),
+        Withdrawals {
+            start_before,
+            limit,
+        } => to_binary(&query::withdrawals(deps, start_before, limit)?),
+        AccruedRewards { address } => to_binary(&query::accrued_rewards(deps, address)?),
+        Offer { offer_id } => to_binary(&query::offer(deps, offer_id)?),
+        OffersByToken {
+            nft_contract,
+            nft_id,
+            start_after,
+            limit,
+        } => to_binary(&query::offers_by_token(
+            deps,
+            nft_contract,
+            nft_id,
+            start_after,
+            limit,
+        )?),
+        OffersByCollection {
+            nft_contract,
+            start_after,
+            limit,
+        } => to_binary(&query::offers_by_collection(
+            deps,
+            nft_contract,
+            start_after,
+            limit,
+        )?),
+        OffersByBuyer {
+            buyer,
+            start_after,
+            limit,
+        } => to_binary(&query::offers_by_buyer(deps, buyer, start_after, limit)?),
+        CollectionOffer { offer_id } => to_binary(&query::collection_offer(deps, offer_id)?),
+        CollectionOffers {
+            nft_contract,
+            start_after,
+            limit,
+        } => to_binary(&query::collection_offers_by_collection(
+            deps,
+            nft_contract,
+            start_after,
+            limit,
+        )?),
+        CollectionOffersByBuyer {
+            buyer,
+            start_after,
+            limit,
+        } => to_binary(&query::collection_offers_by_buyer(
+            deps,
+            buyer,
+            start_after,
+            limit,
+        )?),
+        BestCollectionOffer {
+            nft_contract,
+            denom,
+        } => to_binary(&query::best_collection_offer(
+            deps,
+            env,
+            nft_contract,
+            denom,
+        )?),
     }
 }
 
@@ -43,37 +152,142 @@ pub fn execute(
     msg: ExecMsg,
 ) -> ArchwayResult<ContractError> {
     match msg {
-        ExecMsg::UpdateRewardsAddress { address } => {
-            update_rewards_address(deps, info.sender, address.unwrap_or(env.contract.address))
+        ExecMsg::Propose { action, expires } => propose(deps, env, info.sender, action, expires),
+        ExecMsg::Approve { proposal_id } => approve(deps, env, info.sender, proposal_id),
+        ExecMsg::GrantRole { address, role } => grant_role(deps, info.sender, address, role),
+        ExecMsg::RevokeRole { address, role } => revoke_role(deps, info.sender, address, role),
+        ExecMsg::Pause { operations } => set_paused(deps, info.sender, operations, true),
+        ExecMsg::Unpause { operations } => set_paused(deps, info.sender, operations, false),
+        ExecMsg::WithdrawRewards { limit, record_ids } => {
+            withdraw_rewards(deps, info.sender, limit, record_ids)
         }
-        ExecMsg::WithdrawRewards {} => withdraw_rewards(deps, info.sender),
-        ExecMsg::AddOwner { new_owner } => add_owner(deps, info.sender, new_owner),
-        ExecMsg::RemoveOwner { old_owner } => remove_owner(deps, info.sender, old_owner),
+        ExecMsg::DistributeRewards {} => distribute_rewards(deps, env),
+        ExecMsg::ClaimRewards {} => claim_rewards(deps, info.sender),
+        ExecMsg::UpdateFees { fee_bps } => update_fees(deps, info.sender, fee_bps),
         ExecMsg::CreateAuction {
             nft_id,
             nft_contract,
             min_bid,
             buyout,
             denom,
-        } => create_auction(
-            deps,
-            env.clone(),
-            info.sender,
-            env.block.time.seconds(),
-            nft_id,
-            nft_contract,
+            duration,
+            start_time,
+            gate,
+        } => {
+            let params = AuctionParams {
+                min_bid,
+                buyout,
+                denom,
+                duration,
+                start_time,
+                gate,
+            };
+
+            create_auction(deps, env, info.sender, nft_id, nft_contract, params)
+        }
+        ExecMsg::CreateLotAuction {
+            tokens,
             min_bid,
             buyout,
             denom,
-        ),
-        ExecMsg::Bid {
-            nft_id,
-        } => bid(deps, info.sender, info.funds, nft_id, env.block.time.seconds()),
-        ExecMsg::Buyout {
+            duration,
+            start_time,
+            gate,
+        } => {
+            let params = AuctionParams {
+                min_bid,
+                buyout,
+                denom,
+                duration,
+                start_time,
+                gate,
+            };
+
+            create_lot_auction(deps, env, info.sender, tokens, params)
+        }
+        ExecMsg::BatchCreateAuction { defaults, auctions } => {
+            batch_create_auction(deps, env, info.sender, defaults, auctions)
+        }
+        ExecMsg::Bid { nft_id, proof } => {
+            let blocktime = env.block.time.seconds();
+            bid(deps, info.sender, info.funds, nft_id, blocktime, false, proof)
+        }
+        ExecMsg::ProxyBid { nft_id, proof } => {
+            let blocktime = env.block.time.seconds();
+            bid(deps, info.sender, info.funds, nft_id, blocktime, true, proof)
+        }
+        ExecMsg::UpdateAuction {
             nft_id,
-        } => buyout(deps, info.sender, info.funds, nft_id, env.block.time.seconds()),
+            min_bid,
+            buyout,
+            remove_buyout,
+            extend_by,
+        } => {
+            let update = AuctionUpdate {
+                min_bid,
+                buyout,
+                remove_buyout,
+                extend_by,
+            };
+
+            update_auction(deps, env, info.sender, nft_id, update)
+        }
+        ExecMsg::RetractBid { nft_id } => {
+            retract_bid(deps, info.sender, nft_id, env.block.time.seconds())
+        }
+        ExecMsg::Buyout { nft_id, proof } => {
+            buyout(deps, info.sender, info.funds, nft_id, env.block.time.seconds(), proof)
+        }
         ExecMsg::Close {
             nft_id,
-        } => close(deps, info.sender, env.block.time.seconds(), nft_id),
+        } => close(deps, env.clone(), info.sender, env.block.time.seconds(), nft_id),
+        ExecMsg::ForceCancel { nft_id, reason } => {
+            force_cancel(deps, env, info.sender, nft_id, reason)
+        }
+        ExecMsg::FlagAuction { nft_id, reason } => {
+            flag_auction(deps, env, info.sender, nft_id, Some(reason))
+        }
+        ExecMsg::UnflagAuction { nft_id } => flag_auction(deps, env, info.sender, nft_id, None),
+        ExecMsg::SettleExpired { limit } => settle_expired(deps, env, limit),
+        ExecMsg::DropAuction { nft_id } => drop_auction(deps, env, info.sender, nft_id),
+        ExecMsg::PlaceOffer {
+            nft_id,
+            nft_contract,
+            expires,
+        } => place_offer(deps, env, info.sender, info.funds, nft_id, nft_contract, expires),
+        ExecMsg::CancelOffer { offer_id } => cancel_offer(deps, env, info.sender, offer_id),
+        ExecMsg::PlaceCollectionOffer {
+            nft_contract,
+            quantity,
+            expires,
+        } => place_collection_offer(
+            deps,
+            env,
+            info.sender,
+            info.funds,
+            nft_contract,
+            quantity,
+            expires,
+        ),
+        ExecMsg::WithdrawDeposit {
+            nft_id,
+            nft_contract,
+        } => withdraw_deposit(deps, info.sender, nft_id, nft_contract),
+        ExecMsg::CancelCollectionOffer { offer_id } => {
+            cancel_collection_offer(deps, env, info.sender, offer_id)
+        }
+        ExecMsg::ReceiveNft(msg) => receive_nft(deps, env, info.sender, msg),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut<ArchwayQuery>, env: Env, msg: Reply) -> ArchwayResult<ContractError> {
+    use contract::reply::{self, HOOK_REPLY_ID, SETTLE_REPLY_ID, WITHDRAW_REPLY_ID};
+
+    match msg.id {
+        SETTLE_REPLY_ID => reply::settled(deps, msg),
+        WITHDRAW_REPLY_ID => reply::rewards_withdrawn(deps, env, msg),
+        HOOK_REPLY_ID => reply::hook_failed(msg),
+        id => Err(ContractError::UnknownReply(id)),
     }
 }
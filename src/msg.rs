@@ -1,8 +1,13 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_schema::QueryResponses;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Coin, Timestamp};
+use cw721::Cw721ReceiveMsg;
+use cw_utils::Expiration;
 
-use crate::state::Auction;
+use crate::state::{
+    ArchivedAuction, Auction, CollectionOffer, Config, LotToken, Offer, Operation, Proposal, Role,
+    SplitRecipient, Withdrawal,
+};
 
 pub type Coins = Vec<cosmwasm_std::Coin>;
 
@@ -10,12 +15,129 @@ pub type Coins = Vec<cosmwasm_std::Coin>;
 #[derive(QueryResponses)]
 //We will receive all auctions that are still open/unclaimed
 pub enum QueryMsg {
+    #[returns(Config)]
+    Config {},
+    #[returns(Proposal)]
+    Proposal {
+        proposal_id: u64,
+    },
+    //Proposals still waiting for approvals that have not expired
+    #[returns(ProposalsResp)]
+    PendingProposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(RoleHolder)]
+    RolesOf {
+        address: String,
+    },
+    //Every address holding a role
+    #[returns(RolesResp)]
+    Roles {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(PausedResp)]
+    Paused {},
+    #[returns(Auction)]
+    Auction {
+        nft_id: String,
+    },
+    //Open auctions ordered by `nft_id`
     #[returns(OpenAuctionsResp)]
-    OpenAuctions {},
+    OpenAuctions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    //Open auctions that don't accept bids yet, soonest start first. `start_after` is the `nft_id` of the last auction
+    //of the previous page.
+    #[returns(OpenAuctionsResp)]
+    UpcomingAuctions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    //Expired auctions that failed to settle, ordered by `nft_id`
+    #[returns(FailedSettlementsResp)]
+    FailedSettlements {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(ArchivedAuction)]
+    ArchivedAuction {
+        id: u64,
+    },
+    //Ended auctions, most recent first
+    #[returns(ArchivedAuctionsResp)]
+    ArchivedAuctions {
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
     #[returns(OutstandingRewardsResponse)]
     OutstandingRewards {},
+    #[returns(RewardsSplitResp)]
+    RewardsSplit {},
+    #[returns(HooksResp)]
+    Hooks {},
+    //Past rewards withdrawals, most recent first
+    #[returns(WithdrawalsResp)]
+    Withdrawals {
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    //Rewards the address can claim, and its points in the current epoch
+    #[returns(AccruedRewardsResp)]
+    AccruedRewards {
+        address: String,
+    },
     #[returns(archway_bindings::types::rewards::ContractMetadataResponse)]
     Metadata {},
+    #[returns(archway_bindings::types::rewards::FlatFeeResponse)]
+    FlatFee {},
+    #[returns(Offer)]
+    Offer {
+        offer_id: u64,
+    },
+    #[returns(OffersResp)]
+    OffersByToken {
+        nft_contract: String,
+        nft_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(OffersResp)]
+    OffersByCollection {
+        nft_contract: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(OffersResp)]
+    OffersByBuyer {
+        buyer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(CollectionOffer)]
+    CollectionOffer {
+        offer_id: u64,
+    },
+    #[returns(CollectionOffersResp)]
+    CollectionOffers {
+        nft_contract: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(CollectionOffersResp)]
+    CollectionOffersByBuyer {
+        buyer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    //Highest priced collection offer in `denom` that has not expired. Prices in different denoms can't be compared.
+    #[returns(BestCollectionOfferResp)]
+    BestCollectionOffer {
+        nft_contract: String,
+        denom: String,
+    },
 }
 
 #[cw_serde]
@@ -23,6 +145,79 @@ pub struct OpenAuctionsResp {
     pub auctions: Vec<Auction>,
 }
 
+#[cw_serde]
+pub struct FailedSettlement {
+    pub nft_id: String,
+    pub reason: String,
+}
+
+#[cw_serde]
+pub struct FailedSettlementsResp {
+    pub settlements: Vec<FailedSettlement>,
+}
+
+#[cw_serde]
+pub struct WithdrawalsResp {
+    pub withdrawals: Vec<Withdrawal>,
+}
+
+#[cw_serde]
+pub struct HooksResp {
+    pub hooks: Vec<String>,
+}
+
+#[cw_serde]
+pub struct RewardsSplitResp {
+    pub recipients: Vec<SplitRecipient>,
+}
+
+#[cw_serde]
+pub struct AccruedRewardsResp {
+    pub claimable: Coins,
+    pub pending_points: u64,
+}
+
+#[cw_serde]
+pub struct ArchivedAuctionsResp {
+    pub auctions: Vec<ArchivedAuction>,
+}
+
+#[cw_serde]
+pub struct ProposalsResp {
+    pub proposals: Vec<Proposal>,
+}
+
+#[cw_serde]
+pub struct RoleHolder {
+    pub address: Addr,
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct RolesResp {
+    pub holders: Vec<RoleHolder>,
+}
+
+#[cw_serde]
+pub struct PausedResp {
+    pub operations: Vec<Operation>,
+}
+
+#[cw_serde]
+pub struct OffersResp {
+    pub offers: Vec<Offer>,
+}
+
+#[cw_serde]
+pub struct CollectionOffersResp {
+    pub offers: Vec<CollectionOffer>,
+}
+
+#[cw_serde]
+pub struct BestCollectionOfferResp {
+    pub offer: Option<CollectionOffer>,
+}
+
 #[cw_serde]
 pub struct OutstandingRewardsResponse {
     pub rewards_balance: Coins,
@@ -30,34 +225,327 @@ pub struct OutstandingRewardsResponse {
 }
 
 #[cw_serde]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    pub owners: Vec<String>,
+    //Defaults to the auction house itself
+    pub rewards_address: Option<String>,
+    //Cut of every sale kept by the house, in basis points
+    pub fee_bps: u16,
+    pub fee_recipient: String,
+    //Bounds of an auction duration, in seconds
+    pub min_duration: u64,
+    pub max_duration: u64,
+    pub accepted_denoms: Vec<String>,
+    //Number of owner approvals needed to execute a proposal
+    pub threshold: u32,
+    //Part of the withdrawn rewards shared between sellers and bidders, in basis points
+    #[serde(default)]
+    pub participants_share_bps: u16,
+    //Minimum raise over the current bid, in basis points of it
+    #[serde(default)]
+    pub min_increment_bps: u16,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    //Required when upgrading a house deployed before 0.2.0, which had no config. Owners are kept.
+    pub legacy_config: Option<LegacyConfig>,
+}
 
 #[cw_serde]
-pub enum ExecMsg {
-    WithdrawRewards {},
-    UpdateRewardsAddress {
-        address: Option<Addr>,
-    },
+pub struct LegacyConfig {
+    pub fee_bps: u16,
+    pub fee_recipient: String,
+    pub min_duration: u64,
+    pub max_duration: u64,
+    pub accepted_denoms: Vec<String>,
+}
+
+//Actions reserved to the owners. They are executed once `threshold` owners approved the proposal.
+#[cw_serde]
+pub enum OwnerAction {
     AddOwner {
         new_owner: Addr,
     },
     RemoveOwner {
         old_owner: Addr,
     },
+    UpdateThreshold {
+        threshold: u32,
+    },
+    //Change the house parameters, fields that are not set are left untouched.
+    UpdateConfig {
+        min_duration: Option<u64>,
+        max_duration: Option<u64>,
+        accepted_denoms: Option<Vec<String>>,
+        participants_share_bps: Option<u16>,
+        min_increment_bps: Option<u16>,
+        fee_recipient: Option<String>,
+    },
+    //Address the Archway rewards are withdrawn to, defaults to the house itself
+    UpdateRewardsAddress {
+        address: Option<String>,
+    },
+    //Split the house part of the rewards between recipients, weights must add up to 10000. An empty list sends it all
+    //to the fee recipient again.
+    UpdateRewardsSplit {
+        recipients: Vec<RewardsRecipient>,
+    },
+    //Register a contract receiving `AuctionHookMsg` notifications
+    AddHook {
+        address: String,
+    },
+    RemoveHook {
+        address: String,
+    },
+    //Archway flat fee charged for every message sent to the house, a zero amount removes it.
+    SetFlatFee {
+        amount: Coin,
+    },
+    //Any role can be granted or revoked by the owners, including admin.
+    GrantRole {
+        address: String,
+        role: Role,
+    },
+    RevokeRole {
+        address: String,
+        role: Role,
+    },
+    //Delist an auction like a moderator would, but send the lot to `nft_receiver` instead of its creator, for example
+    //the rightful holder of a stolen NFT.
+    ForceCancel {
+        nft_id: String,
+        reason: String,
+        nft_receiver: String,
+    },
+}
+
+#[cw_serde]
+pub struct RewardsRecipient {
+    pub address: String,
+    pub weight_bps: u16,
+}
+
+#[cw_serde]
+pub enum ExecMsg {
+    //Any owner can propose an owner action, it counts as his approval.
+    Propose {
+        action: OwnerAction,
+        expires: Expiration,
+    },
+    Approve {
+        proposal_id: u64,
+    },
+    //Admin only, for every role but admin.
+    GrantRole {
+        address: String,
+        role: Role,
+    },
+    RevokeRole {
+        address: String,
+        role: Role,
+    },
+    //Pausers only. Cancelling offers and taking back NFTs of auctions without bids stay available while paused.
+    Pause {
+        operations: Vec<Operation>,
+    },
+    Unpause {
+        operations: Vec<Operation>,
+    },
+    //Rewards manager only. When the house is its own rewards address, the participants share of the withdrawn rewards
+    //is accrued to sellers and bidders and the rest is sent to the fee recipient.
+    //Withdraws up to `limit` rewards records (0 or unset for the Archway maximum), or exactly the given `record_ids`.
+    WithdrawRewards {
+        limit: Option<u64>,
+        record_ids: Option<Vec<u64>>,
+    },
+    //Anyone can withdraw the rewards and split them between the configured recipients, the house must be its own rewards address.
+    DistributeRewards {},
+    //Send the rewards accrued by the sender
+    ClaimRewards {},
+    //Fee manager only. The fee recipient is changed by the owners.
+    UpdateFees {
+        fee_bps: u16,
+    },
+    //The token must have been deposited by the sender with `ReceiveMsg::Deposit`. `duration` is in seconds and defaults to
+    //72h (kept within the configured bounds). With a `start_time`, at most `max_duration` ahead, the auction is listed
+    //right away but only accepts bids from that time, and the duration counts from it.
     CreateAuction {
         nft_id: String,
         nft_contract: String,
         min_bid: u64,
         buyout: u64,
         denom: String,
+        duration: Option<u64>,
+        start_time: Option<Timestamp>,
+        gate: Option<BidderGate>,
+    },
+    //Auction several NFTs deposited by the sender as a single lot. The lot is identified by the `nft_id` of its first
+    //token in every other auction message.
+    CreateLotAuction {
+        tokens: Vec<LotToken>,
+        min_bid: u64,
+        buyout: u64,
+        denom: String,
+        duration: Option<u64>,
+        start_time: Option<Timestamp>,
+        gate: Option<BidderGate>,
+    },
+    //Open one auction per entry in a single transaction. Every token must have been deposited by the sender
+    //otherwise nothing is listed. Auctions are identified by `nft_id` alone, so a batch can't list two tokens with the
+    //same id, even from different collections, nor a token sharing its id with an open auction.
+    BatchCreateAuction {
+        defaults: AuctionParams,
+        auctions: Vec<BatchAuctionEntry>,
     },
+    //`proof` is only needed on auctions gated by a merkle root of addresses
     Bid {
         nft_id: String,
+        proof: Option<Vec<String>>,
+    },
+    //The funds sent are the maximum bid. The house bids the minimum needed to lead and raises the bid by the minimum
+    //increment whenever it is outbid, until the maximum is reached.
+    ProxyBid {
+        nft_id: String,
+        proof: Option<Vec<String>>,
+    },
+    //Seller edit of a live auction, `extend_by` is in seconds. See `AuctionUpdate` for what can change once there are bids.
+    UpdateAuction {
+        nft_id: String,
+        min_bid: Option<u64>,
+        buyout: Option<u64>,
+        #[serde(default)]
+        remove_buyout: bool,
+        extend_by: Option<u64>,
+    },
+    //Take back escrowed outbid bids, or the current bid if the auction was edited since it was placed, even while paused
+    RetractBid {
+        nft_id: String,
     },
     Buyout {
         nft_id: String,
+        proof: Option<Vec<String>>,
     },
     Close {
         nft_id: String,
-    }
+    },
+    //Moderators only. Refund the bidders and send the lot back to its creator, see `OwnerAction::ForceCancel` for any
+    //other receiver.
+    ForceCancel {
+        nft_id: String,
+        reason: String,
+    },
+    //Moderators only. Warn bidders about an auction, flagging it again replaces the reason.
+    FlagAuction {
+        nft_id: String,
+        reason: String,
+    },
+    UnflagAuction {
+        nft_id: String,
+    },
+    //Settle up to `limit` expired auctions, oldest end time first. Auctions that fail to settle are recorded and skipped
+    //by later calls.
+    SettleExpired {
+        limit: u32,
+    },
+    //Moderators only. Refund the bidders of an auction that failed to settle and archive it, leaving its lot in
+    //the house.
+    DropAuction {
+        nft_id: String,
+    },
+    //Escrow the sent funds as an offer on any cw721 token, listed or not.
+    PlaceOffer {
+        nft_id: String,
+        nft_contract: String,
+        expires: Expiration,
+    },
+    CancelOffer {
+        offer_id: u64,
+    },
+    //Escrow the sent funds as a bid on `quantity` tokens of a collection, each token is paid `funds / quantity`.
+    PlaceCollectionOffer {
+        nft_contract: String,
+        quantity: u32,
+        expires: Expiration,
+    },
+    //Take back a deposited token that was not listed
+    WithdrawDeposit {
+        nft_id: String,
+        nft_contract: String,
+    },
+    CancelCollectionOffer {
+        offer_id: u64,
+    },
+    //Entry point for cw721 `SendNft`, the attached message is a `ReceiveMsg`.
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+//Pricing shared by the entries of a batch listing
+#[cw_serde]
+pub struct AuctionParams {
+    pub min_bid: u64,
+    pub buyout: u64,
+    pub denom: String,
+    pub duration: Option<u64>,
+    pub start_time: Option<Timestamp>,
+    pub gate: Option<BidderGate>,
+}
+
+//Changes to a live auction. The minimum bid can only be lowered. Changing or removing the buyout price and extending the
+//auction are only possible before the first bid, after it the buyout price can only be lowered above the current bid.
+#[cw_serde]
+pub struct AuctionUpdate {
+    pub min_bid: Option<u64>,
+    pub buyout: Option<u64>,
+    pub remove_buyout: bool,
+    pub extend_by: Option<u64>,
+}
+
+//Restricts who may bid on or buy out an auction. The merkle root is the hex encoded root of a sha256 tree whose leaves are
+//the hashes of the allowed addresses, pairs being hashed in sorted order. Holders must own a token of the collection.
+#[cw_serde]
+pub enum BidderGate {
+    Allowlist { addresses: Vec<String> },
+    MerkleRoot { root: String },
+    Holders { collection: String },
+}
+
+//Token listed in a batch, any price field that is set overrides the batch defaults.
+#[cw_serde]
+pub struct BatchAuctionEntry {
+    pub nft_id: String,
+    pub nft_contract: String,
+    pub min_bid: Option<u64>,
+    pub buyout: Option<u64>,
+    pub denom: Option<String>,
+    pub duration: Option<u64>,
+    pub start_time: Option<Timestamp>,
+    pub gate: Option<BidderGate>,
+}
+
+//Messages that can be attached to a cw721 `SendNft` targeting the auction house.
+#[cw_serde]
+pub enum ReceiveMsg {
+    //List the received token right away, the sender of the NFT becomes the seller. Several `SendNft`
+    //can be grouped in one transaction to list a whole drop.
+    CreateAuction {
+        min_bid: u64,
+        buyout: u64,
+        denom: String,
+        duration: Option<u64>,
+        start_time: Option<Timestamp>,
+        gate: Option<BidderGate>,
+    },
+    //Keep the received token for the sender of the NFT, who can then list it alone, in a lot or in a batch.
+    Deposit {},
+    AcceptOffer {
+        offer_id: u64,
+    },
+    AcceptCollectionOffer {
+        offer_id: u64,
+    },
+    //Fill the best collection offer still valid in the given denom, see `QueryMsg::BestCollectionOffer`.
+    AcceptBestCollectionOffer {
+        denom: String,
+    },
 }
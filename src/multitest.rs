@@ -0,0 +1,279 @@
+//Multi-test setup for the auction house. The house sends `ArchwayMsg` and queries `ArchwayQuery`, which the default `App`
+//can't handle, so they are served by a mocked Archway module. Available to other crates with the `tests` feature.
+mod archway;
+#[cfg(test)]
+mod invariants;
+#[cfg(test)]
+mod tests;
+
+use anyhow::Result as AnyResult;
+use archway_bindings::{ArchwayMsg, ArchwayQuery};
+use cosmwasm_std::{
+    coins,
+    testing::{MockApi, MockStorage},
+    to_binary, Addr, Coin, Empty,
+};
+use cw721::Cw721ExecuteMsg;
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, BankSudo, Contract, ContractWrapper, Executor,
+    SudoMsg, WasmKeeper,
+};
+
+use crate::msg::{ExecMsg, InstantiateMsg, ReceiveMsg};
+
+pub use archway::ArchwayModule;
+
+//Denom accepted by the houses instantiated with `instantiate_house`
+pub const DENOM: &str = "aconst";
+
+pub type ArchwayApp =
+    App<BankKeeper, MockApi, MockStorage, ArchwayModule, WasmKeeper<ArchwayMsg, ArchwayQuery>>;
+
+pub fn mock_app() -> ArchwayApp {
+    AppBuilder::new_custom()
+        .with_custom(ArchwayModule {})
+        .build(|_, _, _| {})
+}
+
+pub fn auction_house_contract() -> Box<dyn Contract<ArchwayMsg, ArchwayQuery>> {
+    let contract = ContractWrapper::new(crate::execute, crate::instantiate, crate::query)
+        .with_reply(crate::reply)
+        .with_migrate(crate::migrate);
+
+    Box::new(contract)
+}
+
+pub fn cw721_contract() -> Box<dyn Contract<ArchwayMsg, ArchwayQuery>> {
+    let contract = ContractWrapper::new_with_empty(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    );
+
+    Box::new(contract)
+}
+
+//House owned by `owner` alone, keeping a 2.5% fee and accepting `DENOM` for auctions between 1 minute and 1 week.
+pub fn instantiate_house(app: &mut ArchwayApp, owner: &Addr) -> Addr {
+    instantiate_multisig_house(app, &[owner], 1)
+}
+
+//House whose proposals need `threshold` of the `owners`, the first one receives the fees.
+pub fn instantiate_multisig_house(app: &mut ArchwayApp, owners: &[&Addr], threshold: u32) -> Addr {
+    let code_id = app.store_code(auction_house_contract());
+    let owner = owners[0];
+
+    let msg = InstantiateMsg {
+        owners: owners.iter().map(|owner| owner.to_string()).collect(),
+        rewards_address: None,
+        fee_bps: 250,
+        fee_recipient: owner.to_string(),
+        min_duration: 60,
+        max_duration: 7 * 24 * 60 * 60,
+        accepted_denoms: vec![DENOM.to_string()],
+        threshold,
+        participants_share_bps: 0,
+        min_increment_bps: 0,
+    };
+
+    app.instantiate_contract(code_id, owner.clone(), &msg, &[], "auction house", None)
+        .unwrap()
+}
+
+pub fn instantiate_cw721(app: &mut ArchwayApp, minter: &Addr) -> Addr {
+    let code_id = app.store_code(cw721_contract());
+
+    let msg = cw721_base::InstantiateMsg {
+        name: "Collection".to_string(),
+        symbol: "COL".to_string(),
+        minter: minter.to_string(),
+    };
+
+    app.instantiate_contract(code_id, minter.clone(), &msg, &[], "collection", None)
+        .unwrap()
+}
+
+pub fn mint_nft(app: &mut ArchwayApp, cw721: &Addr, minter: &Addr, owner: &Addr, nft_id: &str) {
+    let msg = cw721_base::ExecuteMsg::<Option<Empty>, Empty>::Mint {
+        token_id: nft_id.to_string(),
+        owner: owner.to_string(),
+        token_uri: None,
+        extension: None,
+    };
+
+    app.execute_contract(minter.clone(), cw721.clone(), &msg, &[]).unwrap();
+}
+
+//Lists the token by sending it to the house, the seller being its current owner.
+pub fn list_nft(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    cw721: &Addr,
+    seller: &Addr,
+    nft_id: &str,
+    min_bid: u64,
+    buyout: u64,
+) -> AnyResult<AppResponse> {
+    let create_auction = ReceiveMsg::CreateAuction {
+        min_bid,
+        buyout,
+        denom: DENOM.to_string(),
+        duration: None,
+        start_time: None,
+        gate: None,
+    };
+
+    let msg = Cw721ExecuteMsg::SendNft {
+        contract: house.to_string(),
+        token_id: nft_id.to_string(),
+        msg: to_binary(&create_auction)?,
+    };
+
+    app.execute_contract(seller.clone(), cw721.clone(), &msg, &[])
+}
+
+//Sends a token to the house without listing it, the depositor can then list it with any `ExecMsg`.
+pub fn deposit_nft(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    cw721: &Addr,
+    depositor: &Addr,
+    nft_id: &str,
+) -> AnyResult<AppResponse> {
+    let msg = Cw721ExecuteMsg::SendNft {
+        contract: house.to_string(),
+        token_id: nft_id.to_string(),
+        msg: to_binary(&ReceiveMsg::Deposit {})?,
+    };
+
+    app.execute_contract(depositor.clone(), cw721.clone(), &msg, &[])
+}
+
+//Sends the token to the house to accept the given offer
+pub fn accept_offer(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    cw721: &Addr,
+    seller: &Addr,
+    nft_id: &str,
+    offer_id: u64,
+) -> AnyResult<AppResponse> {
+    let msg = Cw721ExecuteMsg::SendNft {
+        contract: house.to_string(),
+        token_id: nft_id.to_string(),
+        msg: to_binary(&ReceiveMsg::AcceptOffer { offer_id })?,
+    };
+
+    app.execute_contract(seller.clone(), cw721.clone(), &msg, &[])
+}
+
+//Bids `amount` of `DENOM`, the bidder must hold the funds (see `fund`).
+pub fn bid(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    bidder: &Addr,
+    nft_id: &str,
+    amount: u128,
+) -> AnyResult<AppResponse> {
+    let msg = ExecMsg::Bid {
+        nft_id: nft_id.to_string(),
+        proof: None,
+    };
+
+    app.execute_contract(bidder.clone(), house.clone(), &msg, &coins(amount, DENOM))
+}
+
+//Proxy bid with a ceiling of `max_bid` of `DENOM`
+pub fn proxy_bid(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    bidder: &Addr,
+    nft_id: &str,
+    max_bid: u128,
+) -> AnyResult<AppResponse> {
+    let msg = ExecMsg::ProxyBid {
+        nft_id: nft_id.to_string(),
+        proof: None,
+    };
+
+    app.execute_contract(bidder.clone(), house.clone(), &msg, &coins(max_bid, DENOM))
+}
+
+pub fn retract_bid(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    bidder: &Addr,
+    nft_id: &str,
+) -> AnyResult<AppResponse> {
+    let msg = ExecMsg::RetractBid {
+        nft_id: nft_id.to_string(),
+    };
+
+    app.execute_contract(bidder.clone(), house.clone(), &msg, &[])
+}
+
+//Buys the auction out with `amount` of `DENOM`, anything above the buyout price is refunded.
+pub fn buyout(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    buyer: &Addr,
+    nft_id: &str,
+    amount: u128,
+) -> AnyResult<AppResponse> {
+    let msg = ExecMsg::Buyout {
+        nft_id: nft_id.to_string(),
+        proof: None,
+    };
+
+    app.execute_contract(buyer.clone(), house.clone(), &msg, &coins(amount, DENOM))
+}
+
+pub fn close(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    sender: &Addr,
+    nft_id: &str,
+) -> AnyResult<AppResponse> {
+    let msg = ExecMsg::Close {
+        nft_id: nft_id.to_string(),
+    };
+
+    app.execute_contract(sender.clone(), house.clone(), &msg, &[])
+}
+
+pub fn fund(app: &mut ArchwayApp, address: &Addr, amount: u128) {
+    let mint = BankSudo::Mint {
+        to_address: address.to_string(),
+        amount: coins(amount, DENOM),
+    };
+
+    app.sudo(SudoMsg::Bank(mint)).unwrap();
+}
+
+pub fn balance(app: &ArchwayApp, address: &Addr) -> u128 {
+    app.wrap()
+        .query_balance(address, DENOM)
+        .unwrap()
+        .amount
+        .u128()
+}
+
+//Moves the chain forward, with one block every 5 seconds.
+pub fn advance_time(app: &mut ArchwayApp, seconds: u64) {
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(seconds);
+        block.height += seconds / 5;
+    });
+}
+
+//Adds a rewards record for `rewards_address`, like Archway does at the end of every block the contract was used in.
+pub fn add_rewards(app: &mut ArchwayApp, rewards_address: &Addr, rewards: Vec<Coin>) {
+    let block = app.block_info();
+
+    app.init_modules(|router, _, storage| {
+        router
+            .custom
+            .add_rewards_record(storage, &block, rewards_address, rewards)
+    })
+    .unwrap();
+}
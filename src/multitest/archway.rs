@@ -0,0 +1,247 @@
+use std::fmt::Debug;
+
+use anyhow::{bail, Result as AnyResult};
+use archway_bindings::{
+    types::rewards::{
+        ContractMetadataResponse, FlatFeeResponse, RewardsRecord, RewardsRecordsResponse,
+        WithdrawRewardsResponse,
+    },
+    ArchwayMsg, ArchwayQuery, PageResponse,
+};
+use cosmwasm_std::{
+    to_binary, Addr, Api, Binary, BlockInfo, Coin, CustomQuery, Empty, Order, Querier, StdResult,
+    Storage,
+};
+use cw_multi_test::{AppResponse, BankSudo, CosmosRouter, Module, SudoMsg};
+use cw_storage_plus::{Item, Map};
+use cw_utils::NativeBalance;
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+//Contract metadata and flat fees, keyed by contract address like in the Archway rewards module
+const REWARDS_ADDRESSES: Map<&str, String> = Map::new("rewards_addresses");
+const FLAT_FEES: Map<&str, Coin> = Map::new("flat_fees");
+
+//Rewards that were calculated but not withdrawn yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct MockRecord {
+    rewards_address: String,
+    rewards: Vec<Coin>,
+    height: u64,
+    time: String,
+}
+
+const RECORDS: Map<u64, MockRecord> = Map::new("rewards_records");
+const RECORD_COUNT: Item<u64> = Item::new("rewards_record_count");
+
+//Number of records withdrawn when no limit is given
+const DEFAULT_RECORDS_LIMIT: u64 = 1000;
+
+//Stands in for the Archway rewards module. Contracts can only change their own metadata and flat fee, and withdraw the
+//records of which they are the rewards address. Withdrawn rewards are minted to the rewards address.
+pub struct ArchwayModule {}
+
+impl ArchwayModule {
+    pub fn add_rewards_record(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        rewards_address: &Addr,
+        rewards: Vec<Coin>,
+    ) -> StdResult<u64> {
+        let id = RECORD_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+        RECORD_COUNT.save(storage, &id)?;
+
+        let record = MockRecord {
+            rewards_address: rewards_address.to_string(),
+            rewards,
+            height: block.height,
+            time: block.time.to_string(),
+        };
+
+        RECORDS.save(storage, id, &record)?;
+
+        Ok(id)
+    }
+
+    fn records_of(
+        storage: &dyn Storage,
+        rewards_address: &str,
+    ) -> StdResult<Vec<(u64, MockRecord)>> {
+        RECORDS
+            .range(storage, None, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref()
+                    .map_or(true, |(_, record)| record.rewards_address == rewards_address)
+            })
+            .collect()
+    }
+}
+
+impl Module for ArchwayModule {
+    type ExecT = ArchwayMsg;
+    type QueryT = ArchwayQuery;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: Self::ExecT,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        match msg {
+            ArchwayMsg::UpdateContractMetadata { rewards_address, .. } => {
+                if let Some(rewards_address) = rewards_address {
+                    REWARDS_ADDRESSES.save(storage, sender.as_str(), &rewards_address)?;
+                }
+
+                Ok(AppResponse::default())
+            }
+            ArchwayMsg::SetFlatFee { flat_fee_amount, .. } => {
+                if flat_fee_amount.amount.is_zero() {
+                    FLAT_FEES.remove(storage, sender.as_str());
+                } else {
+                    FLAT_FEES.save(storage, sender.as_str(), &flat_fee_amount)?;
+                }
+
+                Ok(AppResponse::default())
+            }
+            ArchwayMsg::WithdrawRewards {
+                records_limit,
+                record_ids,
+            } => {
+                let records = Self::records_of(storage, sender.as_str())?;
+
+                let withdrawn: Vec<(u64, MockRecord)> = if record_ids.is_empty() {
+                    let limit = match records_limit {
+                        Some(limit) if limit > 0 => limit,
+                        _ => DEFAULT_RECORDS_LIMIT,
+                    };
+
+                    records.into_iter().take(limit as usize).collect()
+                } else {
+                    for id in record_ids.iter() {
+                        if !records.iter().any(|(record_id, _)| record_id == id) {
+                            bail!("Rewards record {} not found for {}", id, sender);
+                        }
+                    }
+
+                    records
+                        .into_iter()
+                        .filter(|(id, _)| record_ids.contains(id))
+                        .collect()
+                };
+
+                let mut total_rewards = NativeBalance::default();
+
+                for (id, record) in withdrawn.iter() {
+                    RECORDS.remove(storage, *id);
+
+                    for reward in record.rewards.iter() {
+                        total_rewards += reward.clone();
+                    }
+                }
+
+                total_rewards.normalize();
+
+                let total_rewards = total_rewards.into_vec();
+
+                if !total_rewards.is_empty() {
+                    let mint = BankSudo::Mint {
+                        to_address: sender.to_string(),
+                        amount: total_rewards.clone(),
+                    };
+
+                    router.sudo(api, storage, block, SudoMsg::Bank(mint))?;
+                }
+
+                let data = WithdrawRewardsResponse {
+                    records_num: withdrawn.len() as u64,
+                    total_rewards,
+                };
+
+                Ok(AppResponse {
+                    events: vec![],
+                    data: Some(to_binary(&data)?),
+                })
+            }
+            msg => bail!("Unsupported Archway message: {:?}", msg),
+        }
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        _msg: Self::SudoT,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("Sudo is not supported by the Archway module")
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: Self::QueryT,
+    ) -> AnyResult<Binary> {
+        match request {
+            ArchwayQuery::ContractMetadata { contract_address } => {
+                let rewards_address = REWARDS_ADDRESSES
+                    .may_load(storage, &contract_address)?
+                    .unwrap_or_default();
+
+                Ok(to_binary(&ContractMetadataResponse {
+                    owner_address: contract_address,
+                    rewards_address,
+                })?)
+            }
+            ArchwayQuery::FlatFee { contract_address } => {
+                let flat_fee_amount = match FLAT_FEES.may_load(storage, &contract_address)? {
+                    Some(flat_fee_amount) => flat_fee_amount,
+                    None => bail!("No flat fee set for {}", contract_address),
+                };
+
+                Ok(to_binary(&FlatFeeResponse { flat_fee_amount })?)
+            }
+            //Pagination is ignored, every record is returned at once
+            ArchwayQuery::RewardsRecords { rewards_address, .. } => {
+                let records: Vec<RewardsRecord> = Self::records_of(storage, &rewards_address)?
+                    .into_iter()
+                    .map(|(id, record)| RewardsRecord {
+                        id,
+                        rewards_address: record.rewards_address,
+                        rewards: record.rewards,
+                        calculated_height: record.height as i64,
+                        calculated_time: record.time,
+                    })
+                    .collect();
+
+                let pagination = PageResponse {
+                    next_key: None,
+                    total: Some(records.len() as u64),
+                };
+
+                Ok(to_binary(&RewardsRecordsResponse {
+                    records,
+                    pagination: Some(pagination),
+                })?)
+            }
+            request => bail!("Unsupported Archway query: {:?}", request),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//Random sequences of listings, bids, proxy bids, retractions, buyouts, closes, rewards withdrawals, claims and time
+//jumps. After every step the house must hold the escrowed bids and the claimable rewards (plus the rounding dust of the
+//participants shares), every escrowed token must belong to exactly one open auction and no funds may be created or
+//destroyed besides the minted rewards.
+use cosmwasm_std::{coins, Addr, Uint128};
+use cw721::{Cw721QueryMsg, OwnerOfResponse};
+use cw_multi_test::Executor;
+use cw_utils::Expiration;
+use proptest::prelude::*;
+
+use crate::{
+    msg::{AccruedRewardsResp, ExecMsg, OpenAuctionsResp, OwnerAction, QueryMsg},
+    state::Role,
+};
+
+use super::{
+    add_rewards, advance_time, balance, bid, buyout, close, fund, instantiate_cw721,
+    instantiate_house, list_nft, mint_nft, mock_app, proxy_bid, retract_bid, ArchwayApp, DENOM,
+};
+
+const USERS: usize = 3;
+const TOKENS: usize = 4;
+const INITIAL_FUNDS: u128 = 1_000_000;
+
+#[derive(Clone, Debug)]
+enum Action {
+    Create {
+        seller: usize,
+        token: usize,
+        min_bid: u64,
+        buyout: u64,
+    },
+    Bid {
+        bidder: usize,
+        token: usize,
+        amount: u128,
+    },
+    ProxyBid {
+        bidder: usize,
+        token: usize,
+        max_bid: u128,
+    },
+    Retract {
+        bidder: usize,
+        token: usize,
+    },
+    Buyout {
+        buyer: usize,
+        token: usize,
+        amount: u128,
+    },
+    Close {
+        sender: usize,
+        token: usize,
+    },
+    //The Archway module mints rewards for the house, which the owner withdraws right away
+    Rewards {
+        amount: u128,
+    },
+    Claim {
+        user: usize,
+    },
+    Advance {
+        seconds: u64,
+    },
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        (0..USERS, 0..TOKENS, 1..1_000u64, 1..3_000u64).prop_map(
+            |(seller, token, min_bid, buyout)| Action::Create {
+                seller,
+                token,
+                min_bid,
+                buyout,
+            }
+        ),
+        (0..USERS, 0..TOKENS, 1..3_000u128).prop_map(|(bidder, token, amount)| Action::Bid {
+            bidder,
+            token,
+            amount,
+        }),
+        (0..USERS, 0..TOKENS, 1..3_000u128).prop_map(|(bidder, token, max_bid)| {
+            Action::ProxyBid {
+                bidder,
+                token,
+                max_bid,
+            }
+        }),
+        (0..USERS, 0..TOKENS).prop_map(|(bidder, token)| Action::Retract { bidder, token }),
+        (0..USERS, 0..TOKENS, 1..4_000u128).prop_map(|(buyer, token, amount)| Action::Buyout {
+            buyer,
+            token,
+            amount,
+        }),
+        (0..USERS, 0..TOKENS).prop_map(|(sender, token)| Action::Close { sender, token }),
+        (1..10_000u128).prop_map(|amount| Action::Rewards { amount }),
+        (0..USERS).prop_map(|user| Action::Claim { user }),
+        (0..200_000u64).prop_map(|seconds| Action::Advance { seconds }),
+    ]
+}
+
+struct Setup {
+    app: ArchwayApp,
+    owner: Addr,
+    house: Addr,
+    cw721: Addr,
+    users: Vec<Addr>,
+    minted: u128,
+    withdrawals: u128,
+}
+
+//Every user starts with the same funds, tokens are dealt between them in turn. Half of the rewards go to the
+//participants.
+fn setup() -> Setup {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    let actions = [
+        OwnerAction::UpdateConfig {
+            min_duration: None,
+            max_duration: None,
+            accepted_denoms: None,
+            participants_share_bps: Some(5_000),
+            min_increment_bps: None,
+            fee_recipient: None,
+        },
+        OwnerAction::GrantRole {
+            address: owner.to_string(),
+            role: Role::RewardsManager,
+        },
+    ];
+
+    for action in actions {
+        let msg = ExecMsg::Propose {
+            action,
+            expires: Expiration::Never {},
+        };
+
+        app.execute_contract(owner.clone(), house.clone(), &msg, &[]).unwrap();
+    }
+
+    let users: Vec<Addr> = (0..USERS)
+        .map(|user| Addr::unchecked(format!("user{}", user)))
+        .collect();
+
+    for user in users.iter() {
+        fund(&mut app, user, INITIAL_FUNDS);
+    }
+
+    for token in 0..TOKENS {
+        mint_nft(&mut app, &cw721, &owner, &users[token % USERS], &token.to_string());
+    }
+
+    Setup {
+        app,
+        owner,
+        house,
+        cw721,
+        users,
+        minted: 0,
+        withdrawals: 0,
+    }
+}
+
+//Failing actions are expected (bids too low, closing someone else's auction...) and reverted by the app.
+fn run(setup: &mut Setup, action: Action) {
+    let Setup {
+        app,
+        owner,
+        house,
+        cw721,
+        users,
+        minted,
+        withdrawals,
+    } = setup;
+
+    let _ = match action {
+        Action::Create {
+            seller,
+            token,
+            min_bid,
+            buyout,
+        } => list_nft(app, house, cw721, &users[seller], &token.to_string(), min_bid, buyout),
+        Action::Bid {
+            bidder,
+            token,
+            amount,
+        } => bid(app, house, &users[bidder], &token.to_string(), amount),
+        Action::ProxyBid {
+            bidder,
+            token,
+            max_bid,
+        } => proxy_bid(app, house, &users[bidder], &token.to_string(), max_bid),
+        Action::Retract { bidder, token } => {
+            retract_bid(app, house, &users[bidder], &token.to_string())
+        }
+        Action::Buyout {
+            buyer,
+            token,
+            amount,
+        } => buyout(app, house, &users[buyer], &token.to_string(), amount),
+        Action::Close { sender, token } => close(app, house, &users[sender], &token.to_string()),
+        Action::Rewards { amount } => {
+            add_rewards(app, house, coins(amount, DENOM));
+
+            let msg = ExecMsg::WithdrawRewards {
+                limit: None,
+                record_ids: None,
+            };
+
+            //Withdrawing never fails, the rewards are only minted once withdrawn
+            let resp = app.execute_contract(owner.clone(), house.clone(), &msg, &[]).unwrap();
+            *minted += amount;
+            *withdrawals += 1;
+            Ok(resp)
+        }
+        Action::Claim { user } => {
+            app.execute_contract(users[user].clone(), house.clone(), &ExecMsg::ClaimRewards {}, &[])
+        }
+        Action::Advance { seconds } => {
+            advance_time(app, seconds);
+            Ok(Default::default())
+        }
+    };
+}
+
+fn check_invariants(setup: &Setup) {
+    let Setup {
+        app,
+        owner,
+        house,
+        cw721,
+        users,
+        minted,
+        withdrawals,
+    } = setup;
+
+    let msg = QueryMsg::OpenAuctions {
+        start_after: None,
+        limit: None,
+    };
+
+    let open: OpenAuctionsResp = app.wrap().query_wasm_smart(house, &msg).unwrap();
+
+    //The house holds the escrowed bids and the unclaimed rewards. Sharing a withdrawal between the participants leaves
+    //less than one unit per participant in the house.
+    let escrowed: u128 = open
+        .auctions
+        .iter()
+        .flat_map(|auction| auction.escrowed_bids())
+        .map(|bid| bid.amount.amount.u128())
+        .sum();
+
+    let claimable: u128 = users
+        .iter()
+        .map(|user| {
+            let msg = QueryMsg::AccruedRewards {
+                address: user.to_string(),
+            };
+
+            let accrued: AccruedRewardsResp = app.wrap().query_wasm_smart(house, &msg).unwrap();
+
+            accrued
+                .claimable
+                .iter()
+                .map(|reward| reward.amount)
+                .sum::<Uint128>()
+                .u128()
+        })
+        .sum();
+
+    let held = balance(app, house);
+    assert!(held >= escrowed + claimable);
+    assert!(held - escrowed - claimable <= withdrawals * USERS as u128);
+
+    //A token held by the house is in exactly one open auction, and every open auction holds its tokens
+    for token in 0..TOKENS {
+        let msg = Cw721QueryMsg::OwnerOf {
+            token_id: token.to_string(),
+            include_expired: None,
+        };
+
+        let owner_of: OwnerOfResponse = app.wrap().query_wasm_smart(cw721, &msg).unwrap();
+
+        let auctions = open
+            .auctions
+            .iter()
+            .filter(|auction| auction.tokens().iter().any(|t| t.nft_id == token.to_string()))
+            .count();
+
+        if owner_of.owner == house.as_str() {
+            assert_eq!(auctions, 1);
+        } else {
+            assert_eq!(auctions, 0);
+        }
+    }
+
+    //Funds only move between the users, the house and the fee recipient, the only ones created are the rewards
+    let total: u128 = users.iter().map(|user| balance(app, user)).sum::<u128>()
+        + balance(app, house)
+        + balance(app, owner);
+
+    assert_eq!(total, INITIAL_FUNDS * USERS as u128 + minted);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn escrow_stays_solvent(actions in prop::collection::vec(action(), 1..40)) {
+        let mut setup = setup();
+
+        for action in actions {
+            run(&mut setup, action);
+            check_invariants(&setup);
+        }
+    }
+}
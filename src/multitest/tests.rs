@@ -0,0 +1,1875 @@
+use archway_bindings::{
+    types::rewards::{ContractMetadataResponse, FlatFeeResponse},
+    ArchwayMsg, ArchwayQuery,
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, coins, to_binary, Addr, Binary, Coin, Empty, Response, StdError, StdResult, Timestamp,
+};
+use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, Cw721ReceiveMsg, OwnerOfResponse};
+use cw_multi_test::{AppResponse, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+use cw_utils::Expiration;
+use schemars::schema::Schema;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::ContractError,
+    events::event_schema,
+    helpers::AuctionHouseContract,
+    hooks::AuctionHookMsg,
+    msg::{
+        AccruedRewardsResp, ArchivedAuctionsResp, AuctionParams, BatchAuctionEntry,
+        BestCollectionOfferResp, BidderGate, ExecMsg, FailedSettlementsResp, HooksResp,
+        LegacyConfig, MigrateMsg, OpenAuctionsResp, OwnerAction, QueryMsg, ReceiveMsg,
+        RewardsRecipient, WithdrawalsResp,
+    },
+    state::{
+        CollectionOffer, Config, LotToken, Operation, Outcome, Proposal, Role, WithdrawalMode,
+    },
+};
+
+use super::{
+    accept_offer, add_rewards, advance_time, auction_house_contract, balance, bid, buyout, close,
+    deposit_nft, fund, instantiate_cw721, instantiate_house, instantiate_multisig_house, list_nft,
+    mint_nft, mock_app, proxy_bid, retract_bid, ArchwayApp, DENOM,
+};
+
+fn owner_of(app: &ArchwayApp, cw721: &Addr, nft_id: &str) -> String {
+    let msg = Cw721QueryMsg::OwnerOf {
+        token_id: nft_id.to_string(),
+        include_expired: None,
+    };
+
+    let resp: OwnerOfResponse = app.wrap().query_wasm_smart(cw721, &msg).unwrap();
+    resp.owner
+}
+
+fn propose(app: &mut ArchwayApp, house: &Addr, owner: &Addr, action: OwnerAction) {
+    let msg = ExecMsg::Propose {
+        action,
+        expires: Expiration::Never {},
+    };
+
+    app.execute_contract(owner.clone(), house.clone(), &msg, &[]).unwrap();
+}
+
+#[test]
+fn owners_set_flat_fee() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let house = instantiate_house(&mut app, &owner);
+
+    let action = OwnerAction::SetFlatFee {
+        amount: coin(1_000, DENOM),
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    let resp: FlatFeeResponse = app
+        .wrap()
+        .query_wasm_smart(house, &QueryMsg::FlatFee {})
+        .unwrap();
+
+    assert_eq!(resp.flat_fee_amount, coin(1_000, DENOM));
+}
+
+#[test]
+fn only_owners_set_flat_fee() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let house = instantiate_house(&mut app, &owner);
+
+    let msg = ExecMsg::Propose {
+        action: OwnerAction::SetFlatFee {
+            amount: coin(1_000, DENOM),
+        },
+        expires: Expiration::Never {},
+    };
+
+    let err = app
+        .execute_contract(Addr::unchecked("stranger"), house.clone(), &msg, &[])
+        .unwrap_err();
+
+    assert_eq!(ContractError::Unauthorized, err.downcast().unwrap());
+
+    let resp: Result<FlatFeeResponse, _> =
+        app.wrap().query_wasm_smart(house, &QueryMsg::FlatFee {});
+
+    assert!(resp.is_err());
+}
+
+#[test]
+fn winning_bid_is_paid_to_seller() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &bidder, 1_000);
+    bid(&mut app, &house, &bidder, "1", 1_000).unwrap();
+
+    let msg = QueryMsg::OpenAuctions {
+        start_after: None,
+        limit: None,
+    };
+
+    let resp: OpenAuctionsResp = app.wrap().query_wasm_smart(house.clone(), &msg).unwrap();
+
+    assert_eq!(resp.auctions.len(), 1);
+    assert_eq!(resp.auctions[0].current_bid, Some(coin(1_000, DENOM)));
+
+    //The auction lasts 72h by default
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+
+    let msg = ExecMsg::SettleExpired { limit: 10 };
+    app.execute_contract(bidder.clone(), house.clone(), &msg, &[]).unwrap();
+
+    //2.5% fee
+    assert_eq!(balance(&app, &seller), 975);
+    assert_eq!(balance(&app, &owner), 25);
+    assert_eq!(balance(&app, &house), 0);
+}
+
+//Attribute of the first event of the given type emitted by the house
+fn event_attr(resp: &AppResponse, ty: &str, key: &str) -> String {
+    let event = resp
+        .events
+        .iter()
+        .find(|event| event.ty == format!("wasm-{}", ty))
+        .unwrap();
+
+    event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+        .unwrap()
+}
+
+#[test]
+fn listing_and_settlement_emit_events() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+
+    let listed_at = app.block_info().time;
+    let resp = list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    let end_time = listed_at.plus_seconds(72 * 60 * 60).seconds().to_string();
+
+    assert_eq!(event_attr(&resp, "auction_listed", "auction_id"), "1");
+    assert_eq!(event_attr(&resp, "auction_listed", "collection"), cw721.as_str());
+    assert_eq!(event_attr(&resp, "auction_listed", "seller"), seller.as_str());
+    assert_eq!(event_attr(&resp, "auction_listed", "min_bid"), "100aconst");
+    assert_eq!(event_attr(&resp, "auction_listed", "buyout_price"), "10000aconst");
+    assert_eq!(event_attr(&resp, "auction_listed", "start_time"), "");
+    assert_eq!(event_attr(&resp, "auction_listed", "end_time"), end_time);
+    assert_eq!(event_attr(&resp, "auction_listed", "lot_size"), "1");
+
+    fund(&mut app, &bidder, 500);
+    bid(&mut app, &house, &bidder, "1", 500).unwrap();
+
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+
+    let settled_at = app.block_info().time.seconds().to_string();
+    let resp = close(&mut app, &house, &seller, "1").unwrap();
+
+    assert_eq!(event_attr(&resp, "auction_settled", "auction_id"), "1");
+    assert_eq!(event_attr(&resp, "auction_settled", "collection"), cw721.as_str());
+    assert_eq!(event_attr(&resp, "auction_settled", "seller"), seller.as_str());
+    assert_eq!(event_attr(&resp, "auction_settled", "winner"), bidder.as_str());
+    assert_eq!(event_attr(&resp, "auction_settled", "price"), "500aconst");
+    assert_eq!(event_attr(&resp, "auction_settled", "settled_at"), settled_at);
+}
+
+//Attribute keys of the given event type in the exported events schema, sorted
+fn schema_attribute_keys(ty: &str) -> Vec<String> {
+    let events = event_schema().schema.subschemas.unwrap().one_of.unwrap();
+
+    let event = events
+        .into_iter()
+        .find_map(|event| match event {
+            Schema::Object(event) if event.metadata.as_ref()?.title.as_deref() == Some(ty) => {
+                Some(event)
+            }
+            _ => None,
+        })
+        .unwrap();
+
+    let mut keys: Vec<String> = event.object.unwrap().properties.into_keys().collect();
+    keys.sort();
+    keys
+}
+
+//Attribute keys of the first event of the given type emitted by the house, sorted
+fn emitted_attribute_keys(resp: &AppResponse, ty: &str) -> Vec<String> {
+    let event = resp.events.iter().find(|event| event.ty == ty).unwrap();
+
+    let mut keys: Vec<String> = event
+        .attributes
+        .iter()
+        .map(|attr| attr.key.clone())
+        .filter(|key| key != "_contract_addr")
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn events_schema_matches_emitted_events() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let buyer = Addr::unchecked("buyer");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+
+    let resp = list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    assert_eq!(
+        emitted_attribute_keys(&resp, "wasm-auction_listed"),
+        schema_attribute_keys("wasm-auction_listed")
+    );
+
+    fund(&mut app, &buyer, 500);
+
+    let msg = ExecMsg::PlaceOffer {
+        nft_id: "2".to_string(),
+        nft_contract: cw721.to_string(),
+        expires: Expiration::AtHeight(app.block_info().height + 10),
+    };
+
+    let resp = app.execute_contract(buyer, house, &msg, &coins(500, DENOM)).unwrap();
+
+    assert_eq!(
+        emitted_attribute_keys(&resp, "wasm-offer_placed"),
+        schema_attribute_keys("wasm-offer_placed")
+    );
+
+    let expires = format!("height:{}", app.block_info().height + 10);
+    assert_eq!(event_attr(&resp, "offer_placed", "expires"), expires);
+}
+
+#[test]
+fn outbid_bid_stays_escrowed_until_taken_back() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &first, 500);
+    fund(&mut app, &second, 600);
+
+    bid(&mut app, &house, &first, "1", 500).unwrap();
+
+    let err = bid(&mut app, &house, &second, "1", 500).unwrap_err();
+    assert_eq!(ContractError::BidNotEnough, err.downcast().unwrap());
+
+    bid(&mut app, &house, &second, "1", 600).unwrap();
+
+    assert_eq!(balance(&app, &first), 0);
+    assert_eq!(balance(&app, &house), 1_100);
+
+    //Outbid bidders can take their bid back at any time
+    retract_bid(&mut app, &house, &first, "1").unwrap();
+
+    assert_eq!(balance(&app, &first), 500);
+    assert_eq!(balance(&app, &house), 600);
+
+    let err = retract_bid(&mut app, &house, &first, "1").unwrap_err();
+    assert_eq!(ContractError::NoBidToRetract, err.downcast().unwrap());
+}
+
+#[test]
+fn retracted_bid_gives_the_lead_back_even_when_paused() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &first, 500);
+    fund(&mut app, &second, 600);
+
+    bid(&mut app, &house, &first, "1", 500).unwrap();
+    bid(&mut app, &house, &second, "1", 600).unwrap();
+
+    let err = retract_bid(&mut app, &house, &second, "1").unwrap_err();
+    assert_eq!(ContractError::RetractionNotAllowed, err.downcast().unwrap());
+
+    advance_time(&mut app, 5);
+
+    let msg = ExecMsg::UpdateAuction {
+        nft_id: "1".to_string(),
+        min_bid: None,
+        buyout: Some(5_000),
+        remove_buyout: false,
+        extend_by: None,
+    };
+
+    app.execute_contract(seller, house.clone(), &msg, &[]).unwrap();
+
+    let msg = ExecMsg::Pause {
+        operations: vec![Operation::Bidding, Operation::Settlement],
+    };
+
+    //Owners pause like anyone else, with the pauser role
+    let err = app.execute_contract(owner.clone(), house.clone(), &msg, &[]).unwrap_err();
+    assert_eq!(ContractError::MissingRole(Role::Pauser), err.downcast().unwrap());
+
+    let action = OwnerAction::GrantRole {
+        address: owner.to_string(),
+        role: Role::Pauser,
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    app.execute_contract(owner, house.clone(), &msg, &[]).unwrap();
+
+    let resp = retract_bid(&mut app, &house, &second, "1").unwrap();
+    assert_eq!(
+        event_attr(&resp, "auction_bid_retracted", "current_bidder"),
+        first.as_str()
+    );
+
+    //The previous bidder leads again with his escrowed bid
+    let auction = AuctionHouseContract(house.clone())
+        .query_auction(&app.wrap(), "1")
+        .unwrap();
+    assert_eq!(auction.current_bidder, Some(first.clone()));
+    assert_eq!(auction.current_bid, Some(coin(500, DENOM)));
+
+    assert_eq!(balance(&app, &second), 600);
+    assert_eq!(balance(&app, &house), 500);
+}
+
+#[test]
+fn paused_house_still_refunds() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    for nft_id in ["1", "2", "3", "4"] {
+        mint_nft(&mut app, &cw721, &owner, &seller, nft_id);
+    }
+
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+    list_nft(&mut app, &house, &cw721, &seller, "2", 100, 10_000).unwrap();
+
+    fund(&mut app, &first, 800);
+    fund(&mut app, &second, 10_600);
+
+    bid(&mut app, &house, &first, "1", 500).unwrap();
+    bid(&mut app, &house, &second, "1", 600).unwrap();
+
+    let msg = ExecMsg::PlaceOffer {
+        nft_id: "4".to_string(),
+        nft_contract: cw721.to_string(),
+        expires: Expiration::Never {},
+    };
+
+    app.execute_contract(first.clone(), house.clone(), &msg, &coins(300, DENOM)).unwrap();
+
+    let action = OwnerAction::GrantRole {
+        address: owner.to_string(),
+        role: Role::Pauser,
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    let msg = ExecMsg::Pause {
+        operations: vec![
+            Operation::Creation,
+            Operation::Bidding,
+            Operation::Buyout,
+            Operation::Settlement,
+        ],
+    };
+
+    app.execute_contract(owner, house.clone(), &msg, &[]).unwrap();
+
+    let err = list_nft(&mut app, &house, &cw721, &seller, "3", 100, 10_000).unwrap_err();
+    assert_eq!(ContractError::Paused(Operation::Creation), err.downcast().unwrap());
+
+    let err = bid(&mut app, &house, &second, "1", 700).unwrap_err();
+    assert_eq!(ContractError::Paused(Operation::Bidding), err.downcast().unwrap());
+
+    let err = buyout(&mut app, &house, &second, "1", 10_000).unwrap_err();
+    assert_eq!(ContractError::Paused(Operation::Buyout), err.downcast().unwrap());
+
+    //Outbid bids and offers can be taken back while paused
+    retract_bid(&mut app, &house, &first, "1").unwrap();
+
+    let msg = ExecMsg::CancelOffer { offer_id: 1 };
+    app.execute_contract(first.clone(), house.clone(), &msg, &[]).unwrap();
+
+    assert_eq!(balance(&app, &first), 800);
+
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+
+    let err = close(&mut app, &house, &seller, "1").unwrap_err();
+    assert_eq!(ContractError::Paused(Operation::Settlement), err.downcast().unwrap());
+
+    //Auctions without bids are only returned to their seller
+    close(&mut app, &house, &seller, "2").unwrap();
+
+    assert_eq!(owner_of(&app, &cw721, "2"), seller.to_string());
+    assert_eq!(owner_of(&app, &cw721, "1"), house.to_string());
+    assert_eq!(balance(&app, &house), 600);
+}
+
+#[test]
+fn best_collection_offer_is_the_oldest_highest_price() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let buyer = Addr::unchecked("buyer");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    fund(&mut app, &buyer, 16_700);
+
+    let expires = Expiration::AtTime(app.block_info().time.plus_seconds(60));
+
+    //More expired offers rank above the best valid one than a query page can hold
+    let expired = std::iter::repeat((500, expires)).take(31);
+
+    for (price, expires) in [
+        (400, expires),
+        (300, Expiration::Never {}),
+        (300, Expiration::Never {}),
+        (200, Expiration::Never {}),
+    ]
+    .into_iter()
+    .chain(expired)
+    {
+        let msg = ExecMsg::PlaceCollectionOffer {
+            nft_contract: cw721.to_string(),
+            quantity: 1,
+            expires,
+        };
+
+        app.execute_contract(buyer.clone(), house.clone(), &msg, &coins(price, DENOM))
+            .unwrap();
+    }
+
+    advance_time(&mut app, 61);
+
+    let best_offer = |app: &ArchwayApp, denom: &str| {
+        let msg = QueryMsg::BestCollectionOffer {
+            nft_contract: cw721.to_string(),
+            denom: denom.to_string(),
+        };
+
+        let resp: BestCollectionOfferResp = app.wrap().query_wasm_smart(&house, &msg).unwrap();
+        resp.offer.map(|offer| offer.id)
+    };
+
+    //The highest offers expired
+    assert_eq!(best_offer(&app, DENOM), Some(2));
+    assert_eq!(best_offer(&app, "uother"), None);
+}
+
+#[test]
+fn offer_is_accepted_by_sending_the_nft() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let buyer = Addr::unchecked("buyer");
+    let stranger = Addr::unchecked("stranger");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    mint_nft(&mut app, &cw721, &owner, &seller, "2");
+
+    fund(&mut app, &buyer, 1_300);
+
+    for (nft_id, amount) in [("1", 1_000), ("2", 300)] {
+        let msg = ExecMsg::PlaceOffer {
+            nft_id: nft_id.to_string(),
+            nft_contract: cw721.to_string(),
+            expires: Expiration::Never {},
+        };
+
+        app.execute_contract(buyer.clone(), house.clone(), &msg, &coins(amount, DENOM))
+            .unwrap();
+    }
+
+    assert_eq!(balance(&app, &house), 1_300);
+
+    //The offer is bound to its token
+    let err = accept_offer(&mut app, &house, &cw721, &seller, "2", 1).unwrap_err();
+    assert_eq!(ContractError::WrongNFT, err.downcast().unwrap());
+
+    accept_offer(&mut app, &house, &cw721, &seller, "1", 1).unwrap();
+
+    assert_eq!(owner_of(&app, &cw721, "1"), buyer.to_string());
+
+    //2.5% fee
+    assert_eq!(balance(&app, &seller), 975);
+    assert_eq!(balance(&app, &owner), 25);
+
+    let cancel = ExecMsg::CancelOffer { offer_id: 2 };
+
+    let err = app.execute_contract(stranger, house.clone(), &cancel, &[]).unwrap_err();
+    assert_eq!(ContractError::CannotCancelOffer, err.downcast().unwrap());
+
+    app.execute_contract(buyer.clone(), house.clone(), &cancel, &[]).unwrap();
+
+    assert_eq!(balance(&app, &buyer), 300);
+    assert_eq!(balance(&app, &house), 0);
+}
+
+#[test]
+fn expired_offer_is_refunded_to_the_buyer() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let buyer = Addr::unchecked("buyer");
+    let stranger = Addr::unchecked("stranger");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+
+    fund(&mut app, &buyer, 500);
+
+    let msg = ExecMsg::PlaceOffer {
+        nft_id: "1".to_string(),
+        nft_contract: cw721.to_string(),
+        expires: Expiration::AtTime(app.block_info().time.plus_seconds(60)),
+    };
+
+    app.execute_contract(buyer.clone(), house.clone(), &msg, &coins(500, DENOM)).unwrap();
+
+    advance_time(&mut app, 61);
+
+    let err = accept_offer(&mut app, &house, &cw721, &seller, "1", 1).unwrap_err();
+    assert_eq!(ContractError::OfferExpired, err.downcast().unwrap());
+
+    assert_eq!(owner_of(&app, &cw721, "1"), seller.to_string());
+
+    //Once expired anyone can release the escrow, it always goes back to the buyer
+    let cancel = ExecMsg::CancelOffer { offer_id: 1 };
+    app.execute_contract(stranger.clone(), house.clone(), &cancel, &[]).unwrap();
+
+    assert_eq!(balance(&app, &buyer), 500);
+    assert_eq!(balance(&app, &stranger), 0);
+    assert_eq!(balance(&app, &house), 0);
+}
+
+#[test]
+fn collection_offer_is_filled_token_by_token() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let buyer = Addr::unchecked("buyer");
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &first, "1");
+    mint_nft(&mut app, &cw721, &owner, &second, "2");
+
+    fund(&mut app, &buyer, 1_200);
+
+    let msg = ExecMsg::PlaceCollectionOffer {
+        nft_contract: cw721.to_string(),
+        quantity: 3,
+        expires: Expiration::Never {},
+    };
+
+    app.execute_contract(buyer.clone(), house.clone(), &msg, &coins(1_200, DENOM))
+        .unwrap();
+
+    let accept = |app: &mut ArchwayApp, seller: &Addr, nft_id: &str, msg: ReceiveMsg| {
+        let msg = Cw721ExecuteMsg::SendNft {
+            contract: house.to_string(),
+            token_id: nft_id.to_string(),
+            msg: to_binary(&msg).unwrap(),
+        };
+
+        app.execute_contract(seller.clone(), cw721.clone(), &msg, &[]).unwrap();
+    };
+
+    let msg = ReceiveMsg::AcceptCollectionOffer { offer_id: 1 };
+    accept(&mut app, &first, "1", msg);
+
+    let msg = ReceiveMsg::AcceptBestCollectionOffer {
+        denom: DENOM.to_string(),
+    };
+    accept(&mut app, &second, "2", msg);
+
+    assert_eq!(owner_of(&app, &cw721, "1"), buyer.to_string());
+    assert_eq!(owner_of(&app, &cw721, "2"), buyer.to_string());
+
+    let msg = QueryMsg::CollectionOffer { offer_id: 1 };
+    let resp: CollectionOffer = app.wrap().query_wasm_smart(&house, &msg).unwrap();
+
+    assert_eq!(resp.quantity, 1);
+
+    //Only the unit left is refunded
+    let msg = ExecMsg::CancelCollectionOffer { offer_id: 1 };
+    app.execute_contract(buyer.clone(), house.clone(), &msg, &[]).unwrap();
+
+    assert_eq!(balance(&app, &buyer), 400);
+    assert_eq!(balance(&app, &first), 390);
+    assert_eq!(balance(&app, &second), 390);
+    assert_eq!(balance(&app, &owner), 20);
+    assert_eq!(balance(&app, &house), 0);
+}
+
+#[test]
+fn distributed_rewards_follow_the_split_weights() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let recipients = ["first", "second", "third"].map(Addr::unchecked);
+
+    let house = instantiate_house(&mut app, &owner);
+
+    let err = app
+        .execute_contract(owner.clone(), house.clone(), &ExecMsg::DistributeRewards {}, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::NoRewardsSplit, err.downcast().unwrap());
+
+    let action = OwnerAction::UpdateRewardsSplit {
+        recipients: recipients
+            .iter()
+            .zip([5_000, 3_000, 2_000])
+            .map(|(address, weight_bps)| RewardsRecipient {
+                address: address.to_string(),
+                weight_bps,
+            })
+            .collect(),
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    add_rewards(&mut app, &house, coins(999, DENOM));
+
+    app.execute_contract(owner, house.clone(), &ExecMsg::DistributeRewards {}, &[])
+        .unwrap();
+
+    //The last recipient gets the rounding leftovers
+    let balances = recipients.map(|recipient| balance(&app, &recipient));
+    assert_eq!(balances, [499, 299, 201]);
+    assert_eq!(balance(&app, &house), 0);
+}
+
+#[test]
+fn withdrawn_rewards_are_logged() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let manager = Addr::unchecked("manager");
+
+    let house = instantiate_house(&mut app, &owner);
+
+    let action = OwnerAction::GrantRole {
+        address: manager.to_string(),
+        role: Role::RewardsManager,
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    add_rewards(&mut app, &house, coins(300, DENOM));
+    add_rewards(&mut app, &house, coins(200, DENOM));
+
+    let msg = ExecMsg::WithdrawRewards {
+        limit: None,
+        record_ids: None,
+    };
+
+    app.execute_contract(manager, house.clone(), &msg, &[]).unwrap();
+
+    //Without participants share everything goes to the fee recipient
+    assert_eq!(balance(&app, &owner), 500);
+
+    let msg = QueryMsg::Withdrawals {
+        start_before: None,
+        limit: None,
+    };
+
+    let resp: WithdrawalsResp = app.wrap().query_wasm_smart(house, &msg).unwrap();
+
+    assert_eq!(resp.withdrawals.len(), 1);
+    assert_eq!(resp.withdrawals[0].records, 2);
+    assert_eq!(resp.withdrawals[0].rewards, coins(500, DENOM));
+    assert_eq!(resp.withdrawals[0].mode, Some(WithdrawalMode::Limit { limit: 0 }));
+}
+
+#[test]
+fn activity_is_credited_once_the_auction_settles() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+    let loser = Addr::unchecked("loser");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &seller, 500);
+    fund(&mut app, &bidder, 700);
+    fund(&mut app, &loser, 500);
+
+    let err = bid(&mut app, &house, &seller, "1", 500).unwrap_err();
+    assert_eq!(ContractError::OwnAuction, err.downcast().unwrap());
+
+    let err = buyout(&mut app, &house, &seller, "1", 10_000).unwrap_err();
+    assert_eq!(ContractError::OwnAuction, err.downcast().unwrap());
+
+    bid(&mut app, &house, &loser, "1", 500).unwrap();
+    bid(&mut app, &house, &bidder, "1", 600).unwrap();
+    bid(&mut app, &house, &bidder, "1", 700).unwrap();
+
+    let pending_points = |app: &ArchwayApp, address: &Addr| {
+        let msg = QueryMsg::AccruedRewards {
+            address: address.to_string(),
+        };
+
+        let resp: AccruedRewardsResp = app.wrap().query_wasm_smart(&house, &msg).unwrap();
+        resp.pending_points
+    };
+
+    assert_eq!(pending_points(&app, &bidder), 0);
+
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+    close(&mut app, &house, &seller, "1").unwrap();
+
+    //Every bidder is credited for his own bids, the seller for all of them, both parties for the settlement
+    assert_eq!(pending_points(&app, &loser), 1);
+    assert_eq!(pending_points(&app, &bidder), 3);
+    assert_eq!(pending_points(&app, &seller), 4);
+}
+
+#[test]
+fn helpers_query_auctions() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    for nft_id in ["1", "2", "3"] {
+        mint_nft(&mut app, &cw721, &owner, &seller, nft_id);
+        list_nft(&mut app, &house, &cw721, &seller, nft_id, 100, 10_000).unwrap();
+    }
+
+    let contract = AuctionHouseContract(house);
+    let querier = app.wrap();
+
+    let auction = contract.query_auction(&querier, "2").unwrap();
+    assert_eq!(auction.owner, seller);
+    assert_eq!(auction.min_bid, coin(100, DENOM));
+
+    let page = contract
+        .query_open_auctions(&querier, Some("1".to_string()), Some(1))
+        .unwrap();
+
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].nft_id, "2");
+}
+
+//Hook rejecting every notification
+fn failing_hook() -> Box<dyn Contract<ArchwayMsg, ArchwayQuery>> {
+    let contract = ContractWrapper::new_with_empty(
+        |_, _, _, _: Empty| -> StdResult<Response> { Err(StdError::generic_err("hook down")) },
+        |_, _, _, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+        |_, _, _: Empty| -> StdResult<Binary> { Ok(Binary::default()) },
+    );
+
+    Box::new(contract)
+}
+
+#[test]
+fn failing_hook_does_not_block_auction() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    let code_id = app.store_code(failing_hook());
+    let hook = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "hook", None)
+        .unwrap();
+
+    let action = OwnerAction::AddHook {
+        address: hook.to_string(),
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    let resp: HooksResp = app
+        .wrap()
+        .query_wasm_smart(house.clone(), &QueryMsg::Hooks {})
+        .unwrap();
+
+    assert_eq!(resp.hooks, vec![hook.to_string()]);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &bidder, 500);
+    let resp = bid(&mut app, &house, &bidder, "1", 500).unwrap();
+
+    assert!(resp
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .any(|attr| attr.key == "hook_failed"));
+
+    assert_eq!(balance(&app, &house), 500);
+}
+
+const RECEIVED_HOOK: Item<AuctionHookMsg> = Item::new("received_hook");
+
+#[cw_serde]
+enum HookExecMsg {
+    AuctionHook(AuctionHookMsg),
+}
+
+//Hook contract keeping the last notification it received
+fn recording_hook() -> Box<dyn Contract<ArchwayMsg, ArchwayQuery>> {
+    let contract = ContractWrapper::new_with_empty(
+        |deps, _, _, msg: HookExecMsg| -> StdResult<Response> {
+            let HookExecMsg::AuctionHook(msg) = msg;
+            RECEIVED_HOOK.save(deps.storage, &msg)?;
+            Ok(Response::new())
+        },
+        |_, _, _, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+        |deps, _, _: Empty| -> StdResult<Binary> { to_binary(&RECEIVED_HOOK.load(deps.storage)?) },
+    );
+
+    Box::new(contract)
+}
+
+#[test]
+fn hook_receives_settlement() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    let code_id = app.store_code(recording_hook());
+    let hook = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "hook", None)
+        .unwrap();
+
+    let action = OwnerAction::AddHook {
+        address: hook.to_string(),
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &bidder, 500);
+    bid(&mut app, &house, &bidder, "1", 500).unwrap();
+
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+    close(&mut app, &house, &seller, "1").unwrap();
+
+    let received: AuctionHookMsg = app.wrap().query_wasm_smart(hook, &Empty {}).unwrap();
+
+    assert_eq!(
+        received,
+        AuctionHookMsg::Settled {
+            auction_id: "1".to_string(),
+            seller,
+            winner: Some(bidder),
+            price: Some(coin(500, DENOM)),
+        }
+    );
+}
+
+#[test]
+fn seller_edits_let_current_bidder_retract() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &bidder, 500);
+    bid(&mut app, &house, &bidder, "1", 500).unwrap();
+    advance_time(&mut app, 5);
+
+    let update = |buyout, extend_by| ExecMsg::UpdateAuction {
+        nft_id: "1".to_string(),
+        min_bid: None,
+        buyout,
+        remove_buyout: false,
+        extend_by,
+    };
+
+    //Extending the auction and raising the buyout price hurt the bidder
+    let err = app
+        .execute_contract(seller.clone(), house.clone(), &update(None, Some(60)), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::EditNotAllowed, err.downcast().unwrap());
+
+    let err = app
+        .execute_contract(seller.clone(), house.clone(), &update(Some(20_000), None), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::EditNotAllowed, err.downcast().unwrap());
+
+    app.execute_contract(seller, house.clone(), &update(Some(5_000), None), &[])
+        .unwrap();
+
+    let auction = AuctionHouseContract(house.clone())
+        .query_auction(&app.wrap(), "1")
+        .unwrap();
+    assert_eq!(auction.buyout_price, Some(coin(5_000, DENOM)));
+
+    retract_bid(&mut app, &house, &bidder, "1").unwrap();
+
+    assert_eq!(balance(&app, &bidder), 500);
+    assert_eq!(balance(&app, &house), 0);
+}
+
+#[test]
+fn scheduled_auction_opens_at_start_time() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+
+    let now = app.block_info().time;
+
+    let listing = |start_in| {
+        let create_auction = ReceiveMsg::CreateAuction {
+            min_bid: 100,
+            buyout: 10_000,
+            denom: DENOM.to_string(),
+            duration: None,
+            start_time: Some(now.plus_seconds(start_in)),
+            gate: None,
+        };
+
+        Cw721ExecuteMsg::SendNft {
+            contract: house.to_string(),
+            token_id: "1".to_string(),
+            msg: to_binary(&create_auction).unwrap(),
+        }
+    };
+
+    //The start time can't be further ahead than the maximum duration
+    let msg = listing(8 * 24 * 60 * 60);
+    let err = app.execute_contract(seller.clone(), cw721.clone(), &msg, &[]).unwrap_err();
+    assert_eq!(ContractError::InvalidStartTime, err.downcast().unwrap());
+
+    let msg = listing(60 * 60);
+    app.execute_contract(seller, cw721, &msg, &[]).unwrap();
+
+    let upcoming = QueryMsg::UpcomingAuctions {
+        start_after: None,
+        limit: None,
+    };
+
+    let resp: OpenAuctionsResp = app.wrap().query_wasm_smart(house.clone(), &upcoming).unwrap();
+    assert_eq!(resp.auctions.len(), 1);
+
+    fund(&mut app, &bidder, 500);
+
+    let err = bid(&mut app, &house, &bidder, "1", 500).unwrap_err();
+    assert_eq!(ContractError::NotStarted, err.downcast().unwrap());
+
+    advance_time(&mut app, 60 * 60);
+
+    bid(&mut app, &house, &bidder, "1", 500).unwrap();
+
+    let resp: OpenAuctionsResp = app.wrap().query_wasm_smart(house, &upcoming).unwrap();
+    assert!(resp.auctions.is_empty());
+}
+
+#[test]
+fn proxy_bid_is_raised_up_to_its_ceiling() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let proxy = Addr::unchecked("proxy");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &proxy, 1_000);
+    fund(&mut app, &bidder, 1_200);
+
+    //The proxy bid starts at the minimum bid
+    proxy_bid(&mut app, &house, &proxy, "1", 1_000).unwrap();
+
+    let contract = AuctionHouseContract(house.clone());
+    let auction = contract.query_auction(&app.wrap(), "1").unwrap();
+    assert_eq!(auction.current_bid, Some(coin(100, DENOM)));
+
+    //Bids under the ceiling are outbid right away by the smallest increment
+    bid(&mut app, &house, &bidder, "1", 500).unwrap();
+
+    let auction = contract.query_auction(&app.wrap(), "1").unwrap();
+    assert_eq!(auction.current_bidder, Some(proxy.clone()));
+    assert_eq!(auction.current_bid, Some(coin(501, DENOM)));
+
+    //The outbid bid is refunded right away
+    assert_eq!(balance(&app, &bidder), 1_200);
+
+    bid(&mut app, &house, &bidder, "1", 1_200).unwrap();
+
+    let auction = contract.query_auction(&app.wrap(), "1").unwrap();
+    assert_eq!(auction.current_bidder, Some(bidder.clone()));
+    assert_eq!(auction.current_bid, Some(coin(1_200, DENOM)));
+
+    //The outbid ceiling stays escrowed until the auction ends
+    assert_eq!(balance(&app, &proxy), 0);
+    assert_eq!(balance(&app, &house), 2_200);
+
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+
+    let msg = ExecMsg::Close {
+        nft_id: "1".to_string(),
+    };
+
+    app.execute_contract(seller.clone(), house.clone(), &msg, &[]).unwrap();
+
+    assert_eq!(balance(&app, &proxy), 1_000);
+    assert_eq!(balance(&app, &seller), 1_170);
+    assert_eq!(balance(&app, &house), 0);
+}
+
+#[test]
+fn winning_proxy_bid_excess_is_refunded() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let proxy = Addr::unchecked("proxy");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &proxy, 1_000);
+    fund(&mut app, &bidder, 399);
+
+    proxy_bid(&mut app, &house, &proxy, "1", 1_000).unwrap();
+    bid(&mut app, &house, &bidder, "1", 399).unwrap();
+
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+
+    let msg = ExecMsg::Close {
+        nft_id: "1".to_string(),
+    };
+
+    app.execute_contract(seller.clone(), house.clone(), &msg, &[]).unwrap();
+
+    //The proxy won at 400 and gets back the rest of its ceiling
+    assert_eq!(balance(&app, &proxy), 600);
+    assert_eq!(balance(&app, &bidder), 399);
+    assert_eq!(balance(&app, &seller), 390);
+    assert_eq!(balance(&app, &owner), 10);
+    assert_eq!(balance(&app, &house), 0);
+}
+
+fn list_gated_nft(
+    app: &mut ArchwayApp,
+    house: &Addr,
+    cw721: &Addr,
+    seller: &Addr,
+    nft_id: &str,
+    gate: BidderGate,
+) {
+    let create_auction = ReceiveMsg::CreateAuction {
+        min_bid: 100,
+        buyout: 10_000,
+        denom: DENOM.to_string(),
+        duration: None,
+        start_time: None,
+        gate: Some(gate),
+    };
+
+    let msg = Cw721ExecuteMsg::SendNft {
+        contract: house.to_string(),
+        token_id: nft_id.to_string(),
+        msg: to_binary(&create_auction).unwrap(),
+    };
+
+    app.execute_contract(seller.clone(), cw721.clone(), &msg, &[]).unwrap();
+}
+
+#[test]
+fn merkle_gate_requires_valid_proof() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let allowed = Addr::unchecked("allowed");
+    let other = Addr::unchecked("other");
+    let stranger = Addr::unchecked("stranger");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    let leaf = |addr: &Addr| Sha256::digest(addr.as_bytes()).to_vec();
+    let (allowed_leaf, other_leaf) = (leaf(&allowed), leaf(&other));
+
+    let mut pair = [allowed_leaf, other_leaf.clone()];
+    pair.sort();
+    let root = Sha256::digest(pair.concat());
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+
+    let gate = BidderGate::MerkleRoot {
+        root: hex::encode(root),
+    };
+
+    list_gated_nft(&mut app, &house, &cw721, &seller, "1", gate);
+
+    fund(&mut app, &allowed, 500);
+    fund(&mut app, &stranger, 500);
+
+    let msg = |proof: Vec<u8>| ExecMsg::Bid {
+        nft_id: "1".to_string(),
+        proof: Some(vec![hex::encode(proof)]),
+    };
+
+    let err = app
+        .execute_contract(stranger, house.clone(), &msg(other_leaf.clone()), &coins(500, DENOM))
+        .unwrap_err();
+    assert_eq!(ContractError::NotAllowedToBid, err.downcast().unwrap());
+
+    app.execute_contract(allowed, house.clone(), &msg(other_leaf), &coins(500, DENOM))
+        .unwrap();
+
+    assert_eq!(balance(&app, &house), 500);
+}
+
+#[test]
+fn holders_gate_checks_collection_ownership() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+
+    let gate = BidderGate::Holders {
+        collection: cw721.to_string(),
+    };
+
+    list_gated_nft(&mut app, &house, &cw721, &seller, "1", gate);
+
+    fund(&mut app, &bidder, 500);
+
+    let err = bid(&mut app, &house, &bidder, "1", 500).unwrap_err();
+    assert_eq!(ContractError::NotAllowedToBid, err.downcast().unwrap());
+
+    mint_nft(&mut app, &cw721, &owner, &bidder, "2");
+
+    bid(&mut app, &house, &bidder, "1", 500).unwrap();
+}
+
+#[test]
+fn moderators_flag_auctions() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let moderator = Addr::unchecked("moderator");
+    let seller = Addr::unchecked("seller");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    let flag = ExecMsg::FlagAuction {
+        nft_id: "1".to_string(),
+        reason: "reported as stolen".to_string(),
+    };
+
+    let err = app.execute_contract(moderator.clone(), house.clone(), &flag, &[]).unwrap_err();
+    assert_eq!(ContractError::MissingRole(Role::Moderator), err.downcast().unwrap());
+
+    let action = OwnerAction::GrantRole {
+        address: moderator.to_string(),
+        role: Role::Moderator,
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    app.execute_contract(moderator.clone(), house.clone(), &flag, &[]).unwrap();
+
+    let contract = AuctionHouseContract(house.clone());
+    let flag = contract.query_auction(&app.wrap(), "1").unwrap().flag.unwrap();
+
+    assert_eq!(flag.moderator, moderator);
+    assert_eq!(flag.reason, "reported as stolen");
+
+    let unflag = ExecMsg::UnflagAuction {
+        nft_id: "1".to_string(),
+    };
+
+    app.execute_contract(moderator, house, &unflag, &[]).unwrap();
+
+    let auction = contract.query_auction(&app.wrap(), "1").unwrap();
+    assert_eq!(auction.flag, None);
+}
+
+//House the hostile collection claims owns every token
+const HOSTILE_HOUSE: Item<Addr> = Item::new("house");
+
+//Collection forwarding `SendNft` to the house but rejecting every transfer, so its auctions can never settle.
+fn hostile_cw721() -> Box<dyn Contract<ArchwayMsg, ArchwayQuery>> {
+    let contract = ContractWrapper::new_with_empty(
+        |_, _, info, msg: Cw721ExecuteMsg| -> StdResult<Response> {
+            match msg {
+                Cw721ExecuteMsg::SendNft {
+                    contract,
+                    token_id,
+                    msg,
+                } => {
+                    let receive = Cw721ReceiveMsg {
+                        sender: info.sender.to_string(),
+                        token_id,
+                        msg,
+                    };
+
+                    Ok(Response::new().add_message(receive.into_cosmos_msg(contract)?))
+                }
+                _ => Err(StdError::generic_err("transfer blocked")),
+            }
+        },
+        |deps, _, _, house: String| -> StdResult<Response> {
+            HOSTILE_HOUSE.save(deps.storage, &Addr::unchecked(house))?;
+            Ok(Response::new())
+        },
+        |deps, _, _: Cw721QueryMsg| -> StdResult<Binary> {
+            let owner = HOSTILE_HOUSE.load(deps.storage)?;
+
+            to_binary(&OwnerOfResponse {
+                owner: owner.to_string(),
+                approvals: vec![],
+            })
+        },
+    );
+
+    Box::new(contract)
+}
+
+#[test]
+fn failed_settlements_are_skipped_and_reported() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+    let moderator = Addr::unchecked("moderator");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    let code_id = app.store_code(hostile_cw721());
+    let hostile = app
+        .instantiate_contract(code_id, owner.clone(), &house.to_string(), &[], "hostile", None)
+        .unwrap();
+
+    //The hostile auction ends first
+    list_nft(&mut app, &house, &hostile, &seller, "h1", 100, 10_000).unwrap();
+    advance_time(&mut app, 1);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    list_nft(&mut app, &house, &cw721, &seller, "1", 100, 10_000).unwrap();
+
+    fund(&mut app, &bidder, 500);
+    bid(&mut app, &house, &bidder, "h1", 500).unwrap();
+
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+
+    let attr = |resp: &AppResponse, key: &str| {
+        resp.events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+    };
+
+    let settle = ExecMsg::SettleExpired { limit: 1 };
+
+    let resp = app.execute_contract(owner.clone(), house.clone(), &settle, &[]).unwrap();
+    assert_eq!(attr(&resp, "settle_failed"), Some("h1".to_string()));
+
+    //The next call skips the failed auction
+    let resp = app.execute_contract(owner.clone(), house.clone(), &settle, &[]).unwrap();
+    assert_eq!(attr(&resp, "settled"), Some("1".to_string()));
+
+    let resp = app.execute_contract(owner.clone(), house.clone(), &settle, &[]).unwrap();
+    assert_eq!(attr(&resp, "expired"), Some("0".to_string()));
+
+    let failed = QueryMsg::FailedSettlements {
+        start_after: None,
+        limit: None,
+    };
+
+    let resp: FailedSettlementsResp = app.wrap().query_wasm_smart(&house, &failed).unwrap();
+    assert_eq!(resp.settlements.len(), 1);
+    assert_eq!(resp.settlements[0].nft_id, "h1");
+
+    //Moderators drop it without transferring the token
+    let drop = ExecMsg::DropAuction {
+        nft_id: "h1".to_string(),
+    };
+
+    let err = app.execute_contract(moderator.clone(), house.clone(), &drop, &[]).unwrap_err();
+    assert_eq!(ContractError::MissingRole(Role::Moderator), err.downcast().unwrap());
+
+    let action = OwnerAction::GrantRole {
+        address: moderator.to_string(),
+        role: Role::Moderator,
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    app.execute_contract(moderator.clone(), house.clone(), &drop, &[]).unwrap();
+
+    assert_eq!(balance(&app, &bidder), 500);
+    assert_eq!(balance(&app, &house), 0);
+
+    let msg = QueryMsg::ArchivedAuctions {
+        start_before: None,
+        limit: Some(1),
+    };
+
+    let resp: ArchivedAuctionsResp = app.wrap().query_wasm_smart(&house, &msg).unwrap();
+    assert!(matches!(
+        &resp.auctions[0].outcome,
+        Outcome::Dropped { moderator: dropped_by, .. } if dropped_by == &moderator
+    ));
+
+    let resp: FailedSettlementsResp = app.wrap().query_wasm_smart(&house, &failed).unwrap();
+    assert!(resp.settlements.is_empty());
+}
+
+#[test]
+fn force_cancel_refunds_bidders_and_archives_the_auction() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let moderator = Addr::unchecked("moderator");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+    let rightful = Addr::unchecked("rightful");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    for nft_id in ["1", "2"] {
+        mint_nft(&mut app, &cw721, &owner, &seller, nft_id);
+        list_nft(&mut app, &house, &cw721, &seller, nft_id, 100, 10_000).unwrap();
+    }
+
+    fund(&mut app, &bidder, 500);
+    bid(&mut app, &house, &bidder, "1", 500).unwrap();
+
+    let cancel = ExecMsg::ForceCancel {
+        nft_id: "1".to_string(),
+        reason: "reported as stolen".to_string(),
+    };
+
+    //Owners need the moderator role too
+    let err = app.execute_contract(owner.clone(), house.clone(), &cancel, &[]).unwrap_err();
+    assert_eq!(ContractError::MissingRole(Role::Moderator), err.downcast().unwrap());
+
+    let action = OwnerAction::GrantRole {
+        address: moderator.to_string(),
+        role: Role::Moderator,
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    app.execute_contract(moderator.clone(), house.clone(), &cancel, &[]).unwrap();
+
+    assert_eq!(balance(&app, &bidder), 500);
+    assert_eq!(balance(&app, &house), 0);
+
+    assert_eq!(owner_of(&app, &cw721, "1"), seller.as_str());
+
+    //Any other receiver takes a proposal
+    let action = OwnerAction::ForceCancel {
+        nft_id: "2".to_string(),
+        reason: "reported as stolen".to_string(),
+        nft_receiver: rightful.to_string(),
+    };
+
+    propose(&mut app, &house, &owner, action);
+
+    assert_eq!(owner_of(&app, &cw721, "2"), rightful.as_str());
+
+    let msg = QueryMsg::ArchivedAuctions {
+        start_before: None,
+        limit: None,
+    };
+
+    let resp: ArchivedAuctionsResp = app.wrap().query_wasm_smart(&house, &msg).unwrap();
+    let outcomes: Vec<_> = resp
+        .auctions
+        .into_iter()
+        .map(|archived| (archived.auction.nft_id, archived.outcome))
+        .collect();
+
+    assert_eq!(
+        outcomes,
+        vec![
+            (
+                "2".to_string(),
+                Outcome::Moderated {
+                    moderator: owner,
+                    reason: "reported as stolen".to_string(),
+                    nft_receiver: rightful,
+                }
+            ),
+            (
+                "1".to_string(),
+                Outcome::Moderated {
+                    moderator,
+                    reason: "reported as stolen".to_string(),
+                    nft_receiver: seller,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn rewards_address_changes_take_a_proposal() {
+    let mut app = mock_app();
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+    let treasury = Addr::unchecked("treasury");
+
+    let house = instantiate_multisig_house(&mut app, &[&first, &second], 2);
+
+    let action = OwnerAction::UpdateRewardsAddress {
+        address: Some(treasury.to_string()),
+    };
+
+    propose(&mut app, &house, &first, action);
+
+    let rewards_address = |app: &ArchwayApp| {
+        let resp: ContractMetadataResponse = app
+            .wrap()
+            .query_wasm_smart(&house, &QueryMsg::Metadata {})
+            .unwrap();
+        resp.rewards_address
+    };
+
+    assert_ne!(rewards_address(&app), treasury.as_str());
+
+    let msg = ExecMsg::Approve { proposal_id: 1 };
+    app.execute_contract(second, house.clone(), &msg, &[]).unwrap();
+
+    assert_eq!(rewards_address(&app), treasury.as_str());
+}
+
+#[test]
+fn multisig_rejects_repeated_and_late_approvals() {
+    let mut app = mock_app();
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+    let third = Addr::unchecked("third");
+    let newcomer = Addr::unchecked("newcomer");
+
+    let house = instantiate_multisig_house(&mut app, &[&first, &second, &third], 2);
+
+    let msg = ExecMsg::Propose {
+        action: OwnerAction::AddOwner {
+            new_owner: newcomer.clone(),
+        },
+        expires: Expiration::AtHeight(app.block_info().height + 10),
+    };
+
+    app.execute_contract(first.clone(), house.clone(), &msg, &[]).unwrap();
+
+    //Proposing counts as an approval
+    let approve = ExecMsg::Approve { proposal_id: 1 };
+
+    let err = app.execute_contract(first, house.clone(), &approve, &[]).unwrap_err();
+    assert_eq!(ContractError::AlreadyApproved, err.downcast().unwrap());
+
+    advance_time(&mut app, 60);
+
+    let err = app.execute_contract(second, house.clone(), &approve, &[]).unwrap_err();
+    assert_eq!(ContractError::ProposalExpired, err.downcast().unwrap());
+
+    let config: Config = app
+        .wrap()
+        .query_wasm_smart(&house, &QueryMsg::Config {})
+        .unwrap();
+
+    assert!(!config.owners.contains(&newcomer));
+}
+
+#[test]
+fn approvals_of_removed_owners_are_ignored() {
+    let mut app = mock_app();
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+    let third = Addr::unchecked("third");
+    let newcomer = Addr::unchecked("newcomer");
+
+    let house = instantiate_multisig_house(&mut app, &[&first, &second, &third], 2);
+
+    let action = OwnerAction::AddOwner {
+        new_owner: newcomer.clone(),
+    };
+    propose(&mut app, &house, &third, action);
+
+    let action = OwnerAction::RemoveOwner {
+        old_owner: third.clone(),
+    };
+    propose(&mut app, &house, &first, action);
+
+    let msg = ExecMsg::Approve { proposal_id: 2 };
+    app.execute_contract(second.clone(), house.clone(), &msg, &[]).unwrap();
+
+    let owners = |app: &ArchwayApp| {
+        let config: Config = app
+            .wrap()
+            .query_wasm_smart(&house, &QueryMsg::Config {})
+            .unwrap();
+        config.owners
+    };
+
+    assert_eq!(owners(&app), vec![first.clone(), second.clone()]);
+
+    //The approval of the removed owner is kept but no longer counts
+    let msg = ExecMsg::Approve { proposal_id: 1 };
+    app.execute_contract(first, house.clone(), &msg, &[]).unwrap();
+
+    let msg = QueryMsg::Proposal { proposal_id: 1 };
+    let proposal: Proposal = app.wrap().query_wasm_smart(&house, &msg).unwrap();
+
+    assert_eq!(proposal.approvals.len(), 2);
+    assert!(!owners(&app).contains(&newcomer));
+
+    let msg = ExecMsg::Approve { proposal_id: 1 };
+    app.execute_contract(second, house.clone(), &msg, &[]).unwrap();
+
+    assert!(owners(&app).contains(&newcomer));
+}
+
+#[test]
+fn lot_is_listed_by_its_depositor_and_sold_whole() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let thief = Addr::unchecked("thief");
+    let bidder = Addr::unchecked("bidder");
+
+    let house = instantiate_house(&mut app, &owner);
+    let first = instantiate_cw721(&mut app, &owner);
+    let second = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &first, &owner, &seller, "1");
+    mint_nft(&mut app, &second, &owner, &seller, "2");
+
+    deposit_nft(&mut app, &house, &first, &seller, "1").unwrap();
+    deposit_nft(&mut app, &house, &second, &seller, "2").unwrap();
+
+    let msg = ExecMsg::CreateLotAuction {
+        tokens: vec![
+            LotToken {
+                nft_id: "1".to_string(),
+                nft_contract: first.to_string(),
+            },
+            LotToken {
+                nft_id: "2".to_string(),
+                nft_contract: second.to_string(),
+            },
+        ],
+        min_bid: 100,
+        buyout: 10_000,
+        denom: DENOM.to_string(),
+        duration: None,
+        start_time: None,
+        gate: None,
+    };
+
+    //The house holds the tokens but only their depositor can list them
+    let err = app.execute_contract(thief, house.clone(), &msg, &[]).unwrap_err();
+    assert_eq!(ContractError::NotDepositor, err.downcast().unwrap());
+
+    app.execute_contract(seller.clone(), house.clone(), &msg, &[]).unwrap();
+
+    let auction = AuctionHouseContract(house.clone())
+        .query_auction(&app.wrap(), "1")
+        .unwrap();
+    assert_eq!(auction.owner, seller);
+    assert_eq!(auction.tokens().len(), 2);
+
+    fund(&mut app, &bidder, 500);
+    bid(&mut app, &house, &bidder, "1", 500).unwrap();
+
+    advance_time(&mut app, 72 * 60 * 60 + 1);
+    close(&mut app, &house, &seller, "1").unwrap();
+
+    assert_eq!(owner_of(&app, &first, "1"), bidder.as_str());
+    assert_eq!(owner_of(&app, &second, "2"), bidder.as_str());
+    assert_eq!(balance(&app, &seller), 488);
+    assert_eq!(balance(&app, &owner), 12);
+}
+
+#[test]
+fn depositor_withdraws_unlisted_token() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let thief = Addr::unchecked("thief");
+
+    let house = instantiate_house(&mut app, &owner);
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    mint_nft(&mut app, &cw721, &owner, &seller, "1");
+    deposit_nft(&mut app, &house, &cw721, &seller, "1").unwrap();
+
+    let msg = ExecMsg::WithdrawDeposit {
+        nft_id: "1".to_string(),
+        nft_contract: cw721.to_string(),
+    };
+
+    let err = app.execute_contract(thief, house.clone(), &msg, &[]).unwrap_err();
+    assert_eq!(ContractError::NotDepositor, err.downcast().unwrap());
+
+    app.execute_contract(seller.clone(), house.clone(), &msg, &[]).unwrap();
+    assert_eq!(owner_of(&app, &cw721, "1"), seller.as_str());
+
+    let err = app.execute_contract(seller, house, &msg, &[]).unwrap_err();
+    assert_eq!(ContractError::NotDepositor, err.downcast().unwrap());
+}
+
+#[test]
+fn batch_is_listed_entirely_or_not_at_all() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+
+    let house = instantiate_house(&mut app, &owner);
+    let first = instantiate_cw721(&mut app, &owner);
+    let second = instantiate_cw721(&mut app, &owner);
+
+    for nft_id in ["1", "2", "3"] {
+        mint_nft(&mut app, &first, &owner, &seller, nft_id);
+    }
+
+    mint_nft(&mut app, &second, &owner, &seller, "1");
+
+    deposit_nft(&mut app, &house, &first, &seller, "1").unwrap();
+    deposit_nft(&mut app, &house, &first, &seller, "2").unwrap();
+    deposit_nft(&mut app, &house, &second, &seller, "1").unwrap();
+
+    let entry = |nft_id: &str, nft_contract: &Addr| BatchAuctionEntry {
+        nft_id: nft_id.to_string(),
+        nft_contract: nft_contract.to_string(),
+        min_bid: None,
+        buyout: None,
+        denom: None,
+        duration: None,
+        start_time: None,
+        gate: None,
+    };
+
+    let batch = |auctions: Vec<BatchAuctionEntry>| ExecMsg::BatchCreateAuction {
+        defaults: AuctionParams {
+            min_bid: 100,
+            buyout: 10_000,
+            denom: DENOM.to_string(),
+            duration: None,
+            start_time: None,
+            gate: None,
+        },
+        auctions,
+    };
+
+    //The third token was never deposited, so nothing is listed
+    let msg = batch(vec![entry("1", &first), entry("2", &first), entry("3", &first)]);
+
+    let err = app.execute_contract(seller.clone(), house.clone(), &msg, &[]).unwrap_err();
+    assert_eq!(ContractError::NoNFT, err.downcast().unwrap());
+
+    let contract = AuctionHouseContract(house.clone());
+    let open = contract.query_open_auctions(&app.wrap(), None, None).unwrap();
+    assert!(open.is_empty());
+
+    //Auctions are identified by `nft_id` alone, a token of another collection with the same id can't be listed
+    let msg = batch(vec![entry("1", &first), entry("2", &first), entry("1", &second)]);
+
+    let err = app.execute_contract(seller.clone(), house.clone(), &msg, &[]).unwrap_err();
+    assert_eq!(ContractError::AuctionExists, err.downcast().unwrap());
+
+    let msg = batch(vec![entry("1", &first), entry("2", &first)]);
+    app.execute_contract(seller, house, &msg, &[]).unwrap();
+
+    let open = contract.query_open_auctions(&app.wrap(), None, None).unwrap();
+    assert_eq!(open.len(), 2);
+}
+
+//Auction as stored by the houses deployed before 0.2.0
+#[cw_serde]
+struct LegacyAuction {
+    nft_id: String,
+    nft_contract: String,
+    current_bid: Option<Coin>,
+    current_bidder: Option<Addr>,
+    min_bid: Coin,
+    buyout_price: Option<Coin>,
+    owner: Addr,
+    end_auction: Timestamp,
+}
+
+const LEGACY_OWNERS: Item<Vec<Addr>> = Item::new("owner");
+const LEGACY_AUCTIONS: Item<Vec<LegacyAuction>> = Item::new("open_auctions");
+
+//House as deployed before 0.2.0, instantiated with its owner and a single open auction.
+fn legacy_house() -> Box<dyn Contract<ArchwayMsg, ArchwayQuery>> {
+    let contract = ContractWrapper::new_with_empty(
+        |_, _, _, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+        |deps, _, _, (owner, auction): (Addr, LegacyAuction)| -> StdResult<Response> {
+            cw2::set_contract_version(deps.storage, "auctionhouse", "0.1.0")?;
+            LEGACY_OWNERS.save(deps.storage, &vec![owner])?;
+            LEGACY_AUCTIONS.save(deps.storage, &vec![auction])?;
+            Ok(Response::new())
+        },
+        |_, _, _: Empty| -> StdResult<Binary> { to_binary(&Empty {}) },
+    );
+
+    Box::new(contract)
+}
+
+#[test]
+fn legacy_house_is_migrated_and_settles_its_auctions() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+
+    let cw721 = instantiate_cw721(&mut app, &owner);
+
+    let auction = LegacyAuction {
+        nft_id: "1".to_string(),
+        nft_contract: cw721.to_string(),
+        current_bid: None,
+        current_bidder: None,
+        min_bid: coin(100, DENOM),
+        buyout_price: Some(coin(10_000, DENOM)),
+        owner: seller.clone(),
+        end_auction: app.block_info().time.plus_seconds(60),
+    };
+
+    let legacy_code = app.store_code(legacy_house());
+    let house = app
+        .instantiate_contract(
+            legacy_code,
+            owner.clone(),
+            &(owner.clone(), auction),
+            &[],
+            "legacy house",
+            Some(owner.to_string()),
+        )
+        .unwrap();
+
+    mint_nft(&mut app, &cw721, &owner, &house, "1");
+
+    let msg = MigrateMsg {
+        legacy_config: Some(LegacyConfig {
+            fee_bps: 250,
+            fee_recipient: owner.to_string(),
+            min_duration: 60,
+            max_duration: 7 * 24 * 60 * 60,
+            accepted_denoms: vec![DENOM.to_string()],
+        }),
+    };
+
+    let house_code = app.store_code(auction_house_contract());
+    app.migrate_contract(owner.clone(), house.clone(), &msg, house_code).unwrap();
+
+    let config: Config = app
+        .wrap()
+        .query_wasm_smart(&house, &QueryMsg::Config {})
+        .unwrap();
+
+    assert_eq!(config.owners, vec![owner.clone()]);
+    assert_eq!(config.threshold, 1);
+
+    //The legacy auction is indexed like any other one
+    let contract = AuctionHouseContract(house.clone());
+    let auction = contract.query_auction(&app.wrap(), "1").unwrap();
+    assert_eq!(auction.owner, seller);
+
+    advance_time(&mut app, 61);
+
+    let msg = ExecMsg::SettleExpired { limit: 10 };
+    app.execute_contract(owner, house, &msg, &[]).unwrap();
+
+    assert_eq!(owner_of(&app, &cw721, "1"), seller.to_string());
+}
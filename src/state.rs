@@ -1,27 +1,489 @@
-use cw_storage_plus::Item;
+use std::fmt;
+
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Coin, Timestamp};
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp};
+
+use crate::msg::OwnerAction;
 
 //Auction structure
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Auction {
     pub nft_id: String,
     pub nft_contract: String,
+    //Other tokens sold in the same lot as `nft_id`, the winner receives all of them.
+    #[serde(default)]
+    pub lot: Vec<LotToken>,
     pub current_bid: Option<Coin>,
     pub current_bidder: Option<Addr>,
     pub min_bid: Coin,
-    pub buyout_price: Coin,
+    //Removed by the seller when the auction can't be bought out anymore
+    pub buyout_price: Option<Coin>,
     pub owner: Addr,
     pub end_auction: Timestamp,
+    //Bids are accepted from this time, auctions without start time accept them right away
+    #[serde(default)]
+    pub start_time: Option<Timestamp>,
+    //Only the addresses passing the gate may bid or buy out, anyone can without it
+    #[serde(default)]
+    pub gate: Option<Gate>,
+    //Escrowed bids, oldest first. While the auction has a current bid it is the last one. Outbid bids stay escrowed so
+    //they can lead again if the current bid is retracted, their bidders can take them back at any time.
+    #[serde(default)]
+    pub bid_history: Vec<BidRecord>,
+    //Last time the seller edited the listing
+    #[serde(default)]
+    pub edited_at: Option<Timestamp>,
+    //Warning set by a moderator, for example on a suspected stolen or counterfeit NFT
+    #[serde(default)]
+    pub flag: Option<Flag>,
+}
+
+impl Auction {
+    //Every token escrowed for this auction, starting with the one that identifies it.
+    pub fn tokens(&self) -> Vec<LotToken> {
+        let mut tokens = vec![LotToken {
+            nft_id: self.nft_id.clone(),
+            nft_contract: self.nft_contract.clone(),
+        }];
+        tokens.extend(self.lot.iter().cloned());
+        tokens
+    }
+
+    pub fn has_started(&self, blocktime: u64) -> bool {
+        self.start_time.map_or(true, |start_time| blocktime >= start_time.seconds())
+    }
+
+    //Record of the current bid, its amount is what the house escrows for it. Auctions opened before the bid history was
+    //introduced only know their current bid.
+    pub fn leading_bid(&self) -> Option<BidRecord> {
+        let (bidder, amount) = (self.current_bidder.as_ref()?, self.current_bid.as_ref()?);
+
+        match self.bid_history.last() {
+            Some(last) if &last.bidder == bidder => Some(last.clone()),
+            _ => Some(BidRecord {
+                bidder: bidder.clone(),
+                amount: amount.clone(),
+                placed_at: Timestamp::default(),
+                proxy: false,
+            }),
+        }
+    }
+
+    //Every bid the house escrows for this auction, the current one last
+    pub fn escrowed_bids(&self) -> Vec<BidRecord> {
+        match self.bid_history.last() {
+            Some(_) => self.bid_history.clone(),
+            None => self.leading_bid().into_iter().collect(),
+        }
+    }
+}
+
+//Bidder restriction of an auction. The merkle root is kept as the raw 32 bytes hash.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Gate {
+    Allowlist { addresses: Vec<Addr> },
+    MerkleRoot { root: Binary },
+    Holders { collection: Addr },
+}
+
+//Moderator warning on an auction. Bidding stays possible, delisting is done with a force cancel.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Flag {
+    pub moderator: Addr,
+    pub reason: String,
+    pub flagged_at: Timestamp,
+}
+
+//Escrowed bid. The amount of a proxy bid is the bidder's ceiling, the bid itself is the auction `current_bid` while it
+//leads.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidRecord {
+    pub bidder: Addr,
+    pub amount: Coin,
+    pub placed_at: Timestamp,
+    #[serde(default)]
+    pub proxy: bool,
+}
+
+//Token that is part of an auction lot
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotToken {
+    pub nft_id: String,
+    pub nft_contract: String,
+}
+
+//House parameters set at instantiation.
+//The rewards address receives the rewards from Archway inflation module when they are withdrawn from this contract.
+//When it is the house itself, sellers and bidders can claim a share of the rewards proportional to their activity.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owners: Vec<Addr>,
+    pub rewards_address: Addr,
+    //Cut of every sale kept by the house, in basis points
+    pub fee_bps: u16,
+    pub fee_recipient: Addr,
+    //Bounds of an auction duration, in seconds
+    pub min_duration: u64,
+    pub max_duration: u64,
+    pub accepted_denoms: Vec<String>,
+    //Number of owner approvals needed to execute a proposal
+    pub threshold: u32,
+    //Part of the withdrawn rewards shared between sellers and bidders according to their activity, in basis points.
+    //The rest goes to the fee recipient.
+    #[serde(default)]
+    pub participants_share_bps: u16,
+    //A new bid must beat the current one by at least this part of it, in basis points, and by one unit in any case.
+    //Proxy bids are raised by this increment.
+    #[serde(default)]
+    pub min_increment_bps: u16,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+//Permissions that can be granted to an address. Admins grant and revoke the other roles, admins themselves
+//are appointed by the owners through proposals.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    //Changes the house fee, its recipient is set by the owners
+    FeeManager,
+    //Withdraws rewards to the rewards address set by the owners
+    RewardsManager,
+    //Delists and flags auctions
+    Moderator,
+    //Pauses the house
+    Pauser,
 }
 
-//Contract owner that will receive rewards from Archway inflation module when they are withdrawn from this one.
-//When platform is live this will be a proxy contract address that will have a method for all rewards receivers to claim their proportional rewards.
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let role = match self {
+            Role::Admin => "admin",
+            Role::FeeManager => "fee_manager",
+            Role::RewardsManager => "rewards_manager",
+            Role::Moderator => "moderator",
+            Role::Pauser => "pauser",
+        };
 
-pub const OWNERS: Item<Vec<Addr>> = Item::new("owner");
+        write!(f, "{}", role)
+    }
+}
+
+pub const ROLES: Map<&Addr, Vec<Role>> = Map::new("roles");
+
+//Operations that can be paused independently when a bug is discovered
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    //New auctions and offers
+    Creation,
+    Bidding,
+    Buyout,
+    //Closing an auction with a winning bid and accepting offers
+    Settlement,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operation = match self {
+            Operation::Creation => "creation",
+            Operation::Bidding => "bidding",
+            Operation::Buyout => "buyout",
+            Operation::Settlement => "settlement",
+        };
+
+        write!(f, "{}", operation)
+    }
+}
+
+//Currently paused operations. Refunds and NFT returns are never paused.
+pub const PAUSED: Item<Vec<Operation>> = Item::new("paused");
+
+//Owner action waiting for enough owner approvals before being executed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Addr,
+    pub action: OwnerAction,
+    pub approvals: Vec<Addr>,
+    pub expires: Expiration,
+}
+
+//Pending proposals, they are removed once executed
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+
+//Last proposal id that was assigned
+pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
+
+//Current auctions that are open and/or unclaimed, keyed by `nft_id` and indexed by end time so expired ones can be settled in order.
+//They are also indexed by start time, 0 for the auctions that accepted bids right away, to list the upcoming ones.
+//Every auction message identifies an auction by its bare `nft_id`, so while one is open, tokens with the same id from
+//other collections can't be listed.
 
-//Current auctions that are open and/or unclaimed
+pub struct AuctionIndexes<'a> {
+    pub end_auction: MultiIndex<'a, u64, Auction, String>,
+    pub start_time: MultiIndex<'a, u64, Auction, String>,
+}
+
+impl<'a> IndexList<Auction> for AuctionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Auction>> + '_> {
+        let v: Vec<&dyn Index<Auction>> = vec![&self.end_auction, &self.start_time];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn auctions<'a>() -> IndexedMap<'a, &'a str, Auction, AuctionIndexes<'a>> {
+    let indexes = AuctionIndexes {
+        end_auction: MultiIndex::new(
+            |_pk, auction| auction.end_auction.seconds(),
+            "auctions",
+            "auctions__end_auction",
+        ),
+        start_time: MultiIndex::new(
+            |_pk, auction| auction.start_time.map_or(0, |start_time| start_time.seconds()),
+            "auctions",
+            "auctions__start_time",
+        ),
+    };
+
+    IndexedMap::new("auctions", indexes)
+}
+
+//Tokens sent to the house with `ReceiveMsg::Deposit`, keyed by (`nft_contract`, `nft_id`), with their depositor. Only the
+//depositor can list them or take them back, so a token sent to the house can't be listed by someone else.
+pub const DEPOSITS: Map<(&str, &str), Addr> = Map::new("deposits");
+
+//Open auction escrowing each token, keyed by `(nft_contract, nft_id)`. A token can only be part of one open auction.
+pub const ESCROWED_TOKENS: Map<(&str, &str), String> = Map::new("escrowed_tokens");
+
+//Storage layout of houses deployed before 0.2.0, only read by the migration.
+pub const LEGACY_OWNERS: Item<Vec<Addr>> = Item::new("owner");
+pub const LEGACY_AUCTIONS: Item<Vec<Auction>> = Item::new("open_auctions");
+
+//Auctions picked by `settle_expired` that are waiting for the reply of their settlement, in dispatch order.
+pub const SETTLE_QUEUE: Item<Vec<String>> = Item::new("settle_queue");
+
+//Contracts notified of listings, bids, settlements and cancellations, see `hooks::AuctionHookMsg`
+pub const HOOKS: Item<Vec<Addr>> = Item::new("hooks");
+
+//How an auction ended
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    //The lot went to the winning bidder or to a buyer
+    Sold { buyer: Addr, price: Coin },
+    //Nobody bid, the lot went back to its creator
+    Returned {},
+    //Delisted by the house, the bidder was refunded and the lot sent to `nft_receiver`
+    Moderated {
+        moderator: Addr,
+        reason: String,
+        nft_receiver: Addr,
+    },
+    //Dropped by a moderator after its settlement failed, the bidders were refunded and the lot left in the house
+    Dropped { moderator: Addr, reason: String },
+}
+
+//Auction that is not open anymore, with how it ended
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ArchivedAuction {
+    pub id: u64,
+    pub auction: Auction,
+    pub outcome: Outcome,
+    pub ended_at: Timestamp,
+}
+
+//Auctions that `settle_expired` failed to settle, keyed by `nft_id`, with the error. Later calls skip them until they are
+//closed or dropped by a moderator.
+pub const FAILED_SETTLEMENTS: Map<&str, String> = Map::new("failed_settlements");
+
+//Ended auctions. A token can be auctioned several times so they are keyed by a sequence rather than `nft_id`.
+pub const ARCHIVE: Map<u64, ArchivedAuction> = Map::new("archive");
+
+//Last archive id that was assigned
+pub const ARCHIVE_COUNT: Item<u64> = Item::new("archive_count");
+
+//Rewards are shared per epoch: an epoch collects activity points until the next withdrawal, whose participants share
+//is then split between the addresses that earned points during that epoch.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Epoch {
+    pub id: u64,
+    pub total_points: u64,
+}
+
+pub const CURRENT_EPOCH: Item<Epoch> = Item::new("current_epoch");
+
+//Participants share of the withdrawal that closed an epoch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochRewards {
+    pub total_points: u64,
+    pub rewards: Vec<Coin>,
+}
+
+pub const EPOCH_REWARDS: Map<u64, EpochRewards> = Map::new("epoch_rewards");
+
+//Points earned by an address during its last active epoch. They are converted into claimable rewards once that epoch is closed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Activity {
+    pub epoch: u64,
+    pub points: u64,
+}
+
+pub const ACTIVITY: Map<&Addr, Activity> = Map::new("activity");
+
+//Points gathered by each bidder on an open auction, keyed by `nft_id` and bidder. They are only credited once it settles,
+//to every bidder for his own bids and to the seller for all of them. Bids on an auction that doesn't sell earn nothing.
+pub const AUCTION_ACTIVITY: Map<(&str, &Addr), u64> = Map::new("auction_activity");
+
+//Rewards accrued by an address that were not claimed yet
+pub const CLAIMABLE: Map<&Addr, Vec<Coin>> = Map::new("claimable");
+
+//Receiver of a part of the house rewards, weights of all recipients add up to 10000 basis points
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SplitRecipient {
+    pub address: Addr,
+    pub weight_bps: u16,
+}
+
+//How the house part of the withdrawn rewards is split. When empty it all goes to the fee recipient.
+pub const REWARDS_SPLIT: Item<Vec<SplitRecipient>> = Item::new("rewards_split");
+
+//Rewards records requested from Archway, a limit of 0 withdraws as many records as Archway allows
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalMode {
+    Limit { limit: u64 },
+    RecordIds { ids: Vec<u64> },
+}
+
+//Rewards withdrawn from Archway, as reported in the reply of the withdrawal
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Withdrawal {
+    pub id: u64,
+    pub time: Timestamp,
+    pub rewards: Vec<Coin>,
+    pub records: u64,
+    //Not known for withdrawals logged before it was recorded
+    #[serde(default)]
+    pub mode: Option<WithdrawalMode>,
+}
+
+pub const WITHDRAWALS: Map<u64, Withdrawal> = Map::new("withdrawals");
+
+//Mode of the withdrawal waiting for its reply, logged with it in `reply::rewards_withdrawn`
+pub const PENDING_WITHDRAWAL: Item<WithdrawalMode> = Item::new("pending_withdrawal");
+
+//Last withdrawal id that was assigned
+pub const WITHDRAWAL_COUNT: Item<u64> = Item::new("withdrawal_count");
+
+//Standing offer escrowed by a buyer on any cw721 token, whether it is listed in the house or not.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Offer {
+    pub id: u64,
+    pub nft_id: String,
+    pub nft_contract: String,
+    pub buyer: Addr,
+    pub amount: Coin,
+    pub expires: Expiration,
+}
+
+//Offers are indexed by token, by collection and by buyer so they can be listed in queries.
+pub struct OfferIndexes<'a> {
+    pub token: MultiIndex<'a, (String, String), Offer, u64>,
+    pub collection: MultiIndex<'a, String, Offer, u64>,
+    pub buyer: MultiIndex<'a, Addr, Offer, u64>,
+}
+
+impl<'a> IndexList<Offer> for OfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Offer>> + '_> {
+        let v: Vec<&dyn Index<Offer>> = vec![&self.token, &self.collection, &self.buyer];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn offers<'a>() -> IndexedMap<'a, u64, Offer, OfferIndexes<'a>> {
+    let indexes = OfferIndexes {
+        token: MultiIndex::new(
+            |_pk, offer| (offer.nft_contract.clone(), offer.nft_id.clone()),
+            "offers",
+            "offers__token",
+        ),
+        collection: MultiIndex::new(
+            |_pk, offer| offer.nft_contract.clone(),
+            "offers",
+            "offers__collection",
+        ),
+        buyer: MultiIndex::new(|_pk, offer| offer.buyer.clone(), "offers", "offers__buyer"),
+    };
+
+    IndexedMap::new("offers", indexes)
+}
+
+//Last offer id that was assigned
+pub const OFFER_COUNT: Item<u64> = Item::new("offer_count");
+
+//Escrowed bid on any token of a collection. It can be filled by several sellers, one token at a time, until its quantity runs out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionOffer {
+    pub id: u64,
+    pub nft_contract: String,
+    pub buyer: Addr,
+    //Price paid for each token
+    pub price: Coin,
+    //Number of tokens still wanted, the escrow holds `price * quantity`
+    pub quantity: u32,
+    pub expires: Expiration,
+}
+
+pub struct CollectionOfferIndexes<'a> {
+    pub collection: MultiIndex<'a, String, CollectionOffer, u64>,
+    pub buyer: MultiIndex<'a, Addr, CollectionOffer, u64>,
+    //Collection, denom and `u128::MAX - price`, so that ascending order lists the best offers first and the oldest one
+    //first on equal prices.
+    pub price: MultiIndex<'a, (String, String, u128), CollectionOffer, u64>,
+}
+
+impl<'a> IndexList<CollectionOffer> for CollectionOfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<CollectionOffer>> + '_> {
+        let v: Vec<&dyn Index<CollectionOffer>> = vec![&self.collection, &self.buyer, &self.price];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn collection_offers<'a>() -> IndexedMap<'a, u64, CollectionOffer, CollectionOfferIndexes<'a>>
+{
+    let indexes = CollectionOfferIndexes {
+        collection: MultiIndex::new(
+            |_pk, offer| offer.nft_contract.clone(),
+            "collection_offers",
+            "collection_offers__collection",
+        ),
+        buyer: MultiIndex::new(
+            |_pk, offer| offer.buyer.clone(),
+            "collection_offers",
+            "collection_offers__buyer",
+        ),
+        price: MultiIndex::new(
+            |_pk, offer| {
+                (
+                    offer.nft_contract.clone(),
+                    offer.price.denom.clone(),
+                    u128::MAX - offer.price.amount.u128(),
+                )
+            },
+            "collection_offers",
+            "collection_offers__price",
+        ),
+    };
+
+    IndexedMap::new("collection_offers", indexes)
+}
 
-pub const AUCTIONS: Item<Vec<Auction>> = Item::new("open_auctions");
+//Last collection offer id that was assigned
+pub const COLLECTION_OFFER_COUNT: Item<u64> = Item::new("collection_offer_count");